@@ -0,0 +1,32 @@
+// Only memory borrowed by two live places at once may have aliasing
+#[derive(Debug)]
+struct Counter {
+    count: i32,
+}
+
+fn bump(counter: Counter) -> i32 {
+    counter.count + 1
+}
+
+fn main() {
+    // A single borrow
+    let mut x = 1;
+    let y = &mut x;
+    *y += 1;
+
+    // Temporary borrows that end with each call
+    let mut v = Vec::new();
+    v.push(x);
+    v.push(2);
+
+    // A value that is borrowed and then moved
+    let counter = Counter { count: 0 };
+    println!("{:?}", &counter);
+    let _ = bump(counter);
+
+    // Two live borrows of the same memory
+    let mut shared = 5;
+    let raw = &mut shared as *mut i32;
+    let reference = &mut shared;
+    unsafe { *raw += *reference; }
+}
@@ -0,0 +1,12 @@
+// Bodies whose names are one character long or start with a character of more than one byte
+fn f() -> i32 {
+    1
+}
+
+fn été() -> i32 {
+    2
+}
+
+fn main() {
+    let _ = f() + été();
+}
@@ -0,0 +1,18 @@
+// Closures point to the places they capture by reference, and their body is analyzed when they are called
+fn call_with<F: FnMut(&mut i32)>(mut f: F, value: &mut i32) {
+    f(value);
+}
+
+fn main() {
+    let mut x = 1;
+    let raw = &mut x as *mut i32;
+    // The closure holds a mutable borrow of `x` and receives another one
+    let mut add = |v: &mut i32| x += *v;
+    unsafe { add(&mut *raw) };
+
+    // A closure that moves its captures does not point to them
+    let y = 5;
+    let mut z = 7;
+    let add_y = move |v: &mut i32| *v += y;
+    call_with(add_y, &mut z);
+}
@@ -0,0 +1,40 @@
+// Pointers used after the storage or the heap object they point to died
+
+// The box is dropped when the function returns
+fn freed_box() -> *const i32 {
+    let b = Box::new(42);
+    &*b as *const i32
+}
+
+fn freed_in_callee() -> i32 {
+    let p = freed_box();
+    unsafe { *p }
+}
+
+// `x` dies at the end of its scope
+fn out_of_scope() -> i32 {
+    let p: *const i32;
+    {
+        let x = 5;
+        p = &x;
+    }
+    unsafe { *p }
+}
+
+fn after_drop() -> i32 {
+    let b = Box::new(1);
+    let p = &*b as *const i32;
+    drop(b);
+    unsafe { *p }
+}
+
+// The box is still alive
+fn alive() -> i32 {
+    let b = Box::new(1);
+    let p = &*b as *const i32;
+    unsafe { *p }
+}
+
+fn main() {
+    println!("{} {} {} {}", freed_in_callee(), out_of_scope(), after_drop(), alive());
+}
@@ -0,0 +1,8 @@
+// The counter is widened at the loop head, the exit condition keeps it at least 10
+fn main() {
+    let mut i = 0;
+    while i < 10 {
+        i += 1;
+    }
+    let _quotient = 100 / i;
+}
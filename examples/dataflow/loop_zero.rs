@@ -0,0 +1,8 @@
+// The counter is widened at the loop head and may be 10 when the loop exits
+fn main() {
+    let mut i = 0;
+    while i < 10 {
+        i += 1;
+    }
+    let _quotient = 100 / (i - 10);
+}
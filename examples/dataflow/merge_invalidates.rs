@@ -0,0 +1,9 @@
+// The write to `x` in one branch pops `r`, the use after the merge point is reported
+fn main() {
+    let mut x = 0;
+    let r = &mut x;
+    if std::env::args().count() > 1 {
+        x = 1;
+    }
+    *r = 2;
+}
@@ -0,0 +1,11 @@
+// Both branches keep `r` usable
+fn main() {
+    let mut x = 0;
+    let r = &mut x;
+    if std::env::args().count() > 1 {
+        *r = 1;
+    } else {
+        *r = 3;
+    }
+    *r = 2;
+}
@@ -0,0 +1,39 @@
+// Box, Vec and Rc allocate heap objects with their own borrow stacks
+use std::rc::Rc;
+
+fn main() {
+    // A reference into the box invalidates a raw pointer created before it
+    let mut boxed = Box::new(1);
+    let raw = &mut *boxed as *mut i32;
+    let reference = &mut *boxed;
+    *reference = 2;
+    unsafe { *raw = 3 };
+
+    // Each allocation site is a different object
+    let mut first = Box::new(1);
+    let second = Box::new(2);
+    let first_raw = &mut *first as *mut i32;
+    let _ = &*second;
+    unsafe { *first_raw = 4 };
+
+    // Indexing borrows the buffer of the vector through the vector
+    let mut values = Vec::with_capacity(2);
+    values.push(1);
+    values.push(2);
+    let element = &mut values[0] as *mut i32;
+    values[1] = 3;
+    unsafe { *element = 5 };
+
+    // Dropping the vector frees its buffer
+    let mut buffer = Vec::new();
+    buffer.push(1);
+    let start = buffer.as_mut_ptr();
+    unsafe { *start = 2 };
+    drop(buffer);
+    unsafe { *start = 3 };
+
+    // Both counted pointers own the same object
+    let counted = Rc::new(5);
+    let shared = Rc::clone(&counted);
+    println!("{} {}", counted, shared);
+}
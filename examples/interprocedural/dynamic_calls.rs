@@ -0,0 +1,46 @@
+// Calls through trait objects and function pointers execute the bodies the receiver or the pointer may hold
+trait Update {
+    fn update(&mut self, other: &mut i32);
+}
+
+struct Add {
+    total: i32,
+}
+
+// Generic implementation, selected for the type the trait object was made from
+struct Split<T> {
+    parts: T,
+}
+
+impl Update for Add {
+    fn update(&mut self, other: &mut i32) {
+        self.total += *other;
+    }
+}
+
+impl<T: Copy + Into<i32>> Update for Split<T> {
+    fn update(&mut self, other: &mut i32) {
+        *other /= self.parts.into();
+    }
+}
+
+fn update_all(update: &mut dyn Update, other: &mut i32) {
+    update.update(other);
+}
+
+fn double(a: &mut i32, b: &mut i32) {
+    *a *= 2;
+    *b *= 2;
+}
+
+fn main() {
+    let mut n = 2;
+    update_all(&mut Add { total: 0 }, &mut n);
+    update_all(&mut Split { parts: 0u8 }, &mut n);
+
+    // Both copies of the pointer hold `double`, it is analyzed once for the call
+    let f: fn(&mut i32, &mut i32) = double;
+    let g = f;
+    let raw = &mut n as *mut i32;
+    unsafe { g(&mut *raw, &mut *raw) };
+}
@@ -0,0 +1,47 @@
+// Generic functions and trait methods are analyzed in the body of the concrete callee
+trait Update {
+    fn update(&self, a: &mut i32, b: &mut i32);
+}
+
+struct Add;
+struct Keep;
+
+impl Update for Add {
+    fn update(&self, a: &mut i32, b: &mut i32) {
+        *a += *b;
+    }
+}
+
+impl Update for Keep {
+    fn update(&self, _a: &mut i32, _b: &mut i32) {}
+}
+
+fn apply<U: Update>(update: &U, a: &mut i32, b: &mut i32) {
+    update.update(a, b);
+}
+
+fn first<T>(pair: &mut (T, T)) -> &mut T {
+    &mut pair.0
+}
+
+fn swap(a: &mut u8, b: &mut u8) {
+    std::mem::swap(a, b);
+}
+
+fn main() {
+    let mut x = 1;
+    let raw = &mut x as *mut i32;
+    unsafe {
+        // Only the body of `Add` accesses the arguments
+        apply(&Add, &mut *raw, &mut *raw);
+        apply(&Keep, &mut *raw, &mut *raw);
+    }
+
+    // The summary of `first::<u8>` returns the first field
+    let mut pair = (1u8, 2u8);
+    let pair_raw = &mut pair as *mut (u8, u8);
+    unsafe {
+        let f = first(&mut *pair_raw);
+        swap(f, &mut (*pair_raw).0);
+    }
+}
@@ -0,0 +1,43 @@
+// Summaries of recursive functions reach a fixpoint, for self recursion and mutual recursion
+fn is_even(n: u32, steps: &mut u32) -> bool {
+    if n == 0 {
+        true
+    } else {
+        *steps += 1;
+        is_odd(n - 1, steps)
+    }
+}
+
+fn is_odd(n: u32, steps: &mut u32) -> bool {
+    if n == 0 {
+        false
+    } else {
+        is_even(n - 1, steps)
+    }
+}
+
+// The returned reference is one of the arguments after any number of calls
+fn pick<'a>(n: u32, a: &'a mut i32, b: &'a mut i32) -> &'a mut i32 {
+    if n == 0 {
+        a
+    } else {
+        pick(n - 1, b, a)
+    }
+}
+
+fn add(a: &mut i32, b: &mut i32) {
+    *a += *b;
+}
+
+fn main() {
+    let mut steps = 0;
+    is_even(4, &mut steps);
+
+    let mut x = 1;
+    let mut y = 2;
+    let raw = &mut y as *mut i32;
+    unsafe {
+        let picked = pick(3, &mut x, &mut *raw);
+        add(picked, &mut *raw);
+    }
+}
@@ -0,0 +1,27 @@
+// Aliasing created inside a helper is seen by the caller through the summary of the helper
+fn first(pair: &mut (i32, i32)) -> &mut i32 {
+    &mut pair.0
+}
+
+fn second(pair: &mut (i32, i32)) -> &mut i32 {
+    &mut pair.1
+}
+
+fn add(a: &mut i32, b: &mut i32) {
+    *a += *b;
+}
+
+fn main() {
+    let mut pair = (1, 2);
+    let raw = &mut pair as *mut (i32, i32);
+    unsafe {
+        // The summary of `first` returns the first field of its argument
+        let a = first(&mut *raw);
+        add(a, &mut (*raw).0);
+
+        // The summaries keep the fields apart
+        let b = second(&mut *raw);
+        let c = first(&mut *raw);
+        add(b, c);
+    }
+}
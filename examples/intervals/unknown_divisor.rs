@@ -0,0 +1,16 @@
+// Nothing is known about the argument, it may be zero unless a guard excludes it
+fn unknown(n: i32) -> i32 {
+    100 / n
+}
+
+fn guarded(n: i32) -> i32 {
+    if n != 0 {
+        100 / n
+    } else {
+        0
+    }
+}
+
+fn main() {
+    println!("{} {}", unknown(std::env::args().count() as i32), guarded(0));
+}
@@ -0,0 +1,11 @@
+// Pointer casts compare the pointees, other casts compare the values
+fn main() {
+    let value: u64 = 7;
+    let address = &value as *const u64 as usize;
+    let pointer = address as *const u32;
+    let bytes = pointer as *const u8;
+    let truncated = value as u8;
+    let reference = &value;
+    let raw = reference as *const u64;
+    println!("{:?} {} {:?}", bytes, truncated, raw);
+}
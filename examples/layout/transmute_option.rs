@@ -0,0 +1,15 @@
+// Null is the None of an Option of a reference or a non-zero integer, any value is a valid Result
+use std::mem;
+use std::num::NonZeroUsize;
+
+fn main() {
+    let value = 5;
+    let null: usize = 0;
+    let address = &value as *const i32 as usize;
+    let none: Option<&i32> = unsafe { mem::transmute(null) };
+    let some: Option<&i32> = unsafe { mem::transmute(address) };
+    let zero: Option<NonZeroUsize> = unsafe { mem::transmute(null) };
+    let result: Result<u32, u32> = unsafe { mem::transmute(7u64) };
+    let reference: &i32 = unsafe { mem::transmute(null) }; // Error: null reference
+    println!("{:?} {:?} {:?} {:?} {}", none, some, zero, result, reference);
+}
@@ -0,0 +1,17 @@
+// Rvalues whose effect on memory is approximated, strict mode reports them as unsupported
+#![feature(thread_local)]
+
+#[thread_local]
+static mut COUNTER: i32 = 0;
+
+fn main() {
+    // Every element of the array is the same abstract element
+    let mut values = [0; 4];
+    values[1] = 2;
+
+    // Indexing the slice checks the index against its length
+    let slice: &[i32] = &values;
+    let second = slice[1];
+
+    unsafe { COUNTER += second };
+}
@@ -0,0 +1,11 @@
+// Two mutable references to the same location passed to one function
+fn safe(x: &mut i32, y: &mut i32) {
+    *x = 1; // UB: y is protected until safe returns
+    *y = 2; // y was popped by the write through x, reported with the protector violation
+}
+
+fn main() {
+    let mut x = 0;
+    let xraw = &mut x as *mut i32;
+    unsafe { safe(&mut *xraw, &mut *xraw) };
+}
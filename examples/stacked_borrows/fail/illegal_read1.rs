@@ -0,0 +1,8 @@
+// Reading through a raw pointer disables the mutable reference derived from it
+fn main() {
+    let mut x = 15;
+    let xraw = &mut x as *mut i32;
+    let xref = unsafe { &mut *xraw };
+    let _val = unsafe { *xraw };
+    let _val = *xref; // UB in stacked-borrows: xref is disabled, tree borrows keeps it reserved
+}
@@ -0,0 +1,8 @@
+// A shared reference does not grant write access, not even through a raw pointer cast from it
+fn main() {
+    let target = 42;
+    let xref = &target;
+    let x = xref as *const i32 as *mut i32;
+    unsafe { *x = 42 }; // UB: the item of x is SharedReadOnly
+    let _val = *xref;
+}
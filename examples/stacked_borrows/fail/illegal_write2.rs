@@ -0,0 +1,9 @@
+// A new mutable reborrow writes through its parent and pops the raw pointer above it
+fn main() {
+    let mut x = 42;
+    let target = &mut x;
+    let target2 = target as *mut i32;
+    let reborrow = &mut *target;
+    *reborrow = 1;
+    unsafe { *target2 = 13 }; // UB in stacked-borrows: target2 was popped by the reborrow, in tree borrows it is the parent of the reborrow
+}
@@ -0,0 +1,11 @@
+// A write through a raw pointer pops a reference argument that is protected by the running call
+fn inner(_y: &mut i32, x: *mut i32) {
+    unsafe { *x = 0 }; // UB: _y is protected until inner returns
+}
+
+fn main() {
+    let mut x = 0;
+    let xraw = &mut x as *mut i32;
+    let xref = unsafe { &mut *xraw };
+    inner(xref, unsafe { std::ptr::addr_of_mut!(*xraw) });
+}
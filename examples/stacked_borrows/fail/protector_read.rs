@@ -0,0 +1,11 @@
+// A read through a raw pointer disables a mutable reference argument that is protected by the running call
+fn inner(_y: &mut i32, x: *const i32) -> i32 {
+    unsafe { *x } // UB in stacked-borrows: _y is protected until inner returns, tree borrows only forbids writes to it
+}
+
+fn main() {
+    let mut x = 0;
+    let xraw = &mut x as *mut i32;
+    let xref = unsafe { &mut *xraw };
+    let _val = inner(xref, unsafe { std::ptr::addr_of!(*xraw) });
+}
@@ -0,0 +1,8 @@
+// A write through a raw pointer pops the shared references created above it
+fn main() {
+    let mut x = 1;
+    let xraw = &mut x as *mut i32;
+    let shr = unsafe { &*xraw };
+    unsafe { *xraw = 2 };
+    let _val = *shr; // UB: shr was popped by the write
+}
@@ -0,0 +1,9 @@
+// A write through the owner pops the exposed raw pointer, no exposed tag grants the wildcard pointer access
+fn main() {
+    let mut x = 0;
+    let raw = &mut x as *mut i32;
+    let ptr = raw as usize as *mut i32;
+    x = 1;
+    unsafe { *ptr = 2 }; // UB: the exposed tag of raw was popped
+    let _val = x;
+}
@@ -0,0 +1,10 @@
+// A write through a pointer made from an exposed address pops the reference derived after the exposure
+fn main() {
+    let mut x = 0;
+    let raw = &mut x as *mut i32;
+    let addr = raw as usize;
+    let xref = unsafe { &mut *raw };
+    let ptr = addr as *mut i32;
+    unsafe { *ptr = 1 };
+    *xref = 2; // UB: the write through the exposed raw pointer popped xref
+}
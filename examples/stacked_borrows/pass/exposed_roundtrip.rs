@@ -0,0 +1,12 @@
+// A pointer cast back from an exposed address may use the exposed raw pointer
+fn main() {
+    let mut x = 0;
+    let raw = &mut x as *mut i32;
+    let addr = raw as usize;
+    let ptr = addr as *mut i32;
+    unsafe {
+        *ptr = 1;
+        *raw = 2;
+        *ptr = 3;
+    }
+}
@@ -0,0 +1,17 @@
+// Memory returned by foreign code is not tracked, accesses through the wildcard pointer are not checked
+extern "C" {
+    fn malloc(size: usize) -> *mut u8;
+    fn free(ptr: *mut u8);
+}
+
+fn main() {
+    unsafe {
+        let ptr = malloc(4);
+        let copy = ptr;
+        *ptr = 1;
+        *copy = 2;
+        let byte = &mut *ptr;
+        *byte = 3;
+        free(ptr);
+    }
+}
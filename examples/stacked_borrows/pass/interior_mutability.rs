@@ -0,0 +1,11 @@
+// Shared references to an UnsafeCell are SharedReadWrite and can all write
+use std::cell::Cell;
+
+fn main() {
+    let c = Cell::new(1);
+    let r1 = &c;
+    let r2 = &c;
+    r1.set(2);
+    r2.set(3);
+    let _val = r1.get();
+}
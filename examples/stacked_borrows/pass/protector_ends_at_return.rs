@@ -0,0 +1,11 @@
+// The protector of an argument ends when the call returns, the caller can use the raw pointer again
+fn inner(y: &mut i32) {
+    *y = 1;
+}
+
+fn main() {
+    let mut x = 0;
+    let xraw = &mut x as *mut i32;
+    inner(unsafe { &mut *xraw });
+    unsafe { *xraw = 2 };
+}
@@ -0,0 +1,14 @@
+// Raw pointer arguments are not protected, aliasing raw pointers can be used in any order
+fn inner(x: *mut i32, y: *mut i32) {
+    unsafe {
+        *x = 0;
+        *y = 1;
+        *x = 2;
+    }
+}
+
+fn main() {
+    let mut x = 0;
+    let xraw = &mut x as *mut i32;
+    unsafe { inner(std::ptr::addr_of_mut!(*xraw), std::ptr::addr_of_mut!(*xraw)) };
+}
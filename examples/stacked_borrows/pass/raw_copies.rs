@@ -0,0 +1,13 @@
+// Copies of a raw pointer share its permission and can be used in any order
+fn main() {
+    let mut x = 0;
+    let p = &mut x as *mut i32;
+    let q = p;
+    unsafe {
+        *p = 1;
+        *q = 2;
+        *p = 3;
+    }
+    x = 4;
+    let _val = x;
+}
@@ -0,0 +1,7 @@
+// Reading through the owner only disables Unique items, the raw pointer stays usable
+fn main() {
+    let mut x = 1;
+    let p = &mut x as *mut i32;
+    let _val = x;
+    unsafe { *p = 2 };
+}
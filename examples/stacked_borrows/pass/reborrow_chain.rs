@@ -0,0 +1,10 @@
+// Reborrows are used in stack order, each use pops only the reborrows above it
+fn main() {
+    let mut x = 0;
+    let r = &mut x;
+    let r2 = &mut *r;
+    *r2 = 1;
+    *r = 2;
+    x = 3;
+    let _val = x;
+}
@@ -0,0 +1,9 @@
+// Shared references and reads through the owner never invalidate each other
+fn main() {
+    let x = 5;
+    let r1 = &x;
+    let r2 = &x;
+    let _val = *r1 + *r2;
+    let _val = x;
+    let _val = *r1;
+}
@@ -0,0 +1,7 @@
+// The mutable borrow of `v` for `push` is only used after `v.len()` read the vector
+fn main() {
+    let mut v = Vec::new();
+    v.push(0);
+    v.push(v.len());
+    let _val = v[1];
+}
@@ -0,0 +1,13 @@
+// Raw pointers created from the same reference are SharedReadWrite siblings
+fn main() {
+    let mut x = 0;
+    let r = &mut x;
+    let p1 = r as *mut i32;
+    let p2 = r as *mut i32;
+    unsafe {
+        *p1 = 1;
+        *p2 = 2;
+        *p1 = 3;
+    }
+    *r = 4;
+}
@@ -0,0 +1,24 @@
+// The copy reads through the source pointer and writes through the destination pointer
+#![feature(intrinsics)]
+
+extern "rust-intrinsic" {
+    fn copy_nonoverlapping<T>(src: *const T, dst: *mut T, count: usize);
+}
+
+const SOURCE: *const i32 = &5;
+
+fn main() {
+    let mut x = 1;
+    let mut y = 2;
+    let r = &mut y;
+    let raw = &mut *r as *mut i32;
+    let shared = &*r;
+
+    // The write through `raw` pops `shared` from the stack of `y`
+    unsafe { copy_nonoverlapping(&x, raw, 1) };
+    println!("{}", *shared);
+
+    // The read through the constant is not checked
+    unsafe { copy_nonoverlapping(SOURCE, &mut x, 1) };
+    println!("{}", x);
+}
@@ -0,0 +1,13 @@
+// Retags of arguments, of references to promoted constants and of returned references, analyzed with --retag
+fn first(values: &mut [i32; 2]) -> &mut i32 {
+    let all = values;
+    &mut all[0]
+}
+
+fn main() {
+    // The promoted constant is not in the borrow stacks
+    let limit: &i32 = &10;
+    let mut values = [1, 2];
+    // The caller does not know where the returned reference points
+    *first(&mut values) = *limit;
+}
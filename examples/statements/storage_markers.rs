@@ -0,0 +1,29 @@
+// Each iteration gets new storage for `x`, pointers to the storage of the previous iteration have no access to it
+fn previous_iteration() {
+    let mut previous: *mut i32 = std::ptr::null_mut();
+    let mut i = 0;
+    while i < 2 {
+        let mut x = i;
+        if i == 1 {
+            unsafe { *previous = 3 };
+        }
+        previous = &mut x;
+        i += 1;
+    }
+}
+
+// The pointer is used before the storage of `x` dies
+fn same_iteration() {
+    let mut i = 0;
+    while i < 2 {
+        let mut x = i;
+        let current = &mut x as *mut i32;
+        unsafe { *current = 3 };
+        i += 1;
+    }
+}
+
+fn main() {
+    previous_iteration();
+    same_iteration();
+}
@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::stacked_borrows::{Access, Creation, Invalidation, Permission, Stack, Tag, Violation};
+use crate::tree_borrows::Tree;
+
+// Aliasing rules checked on the borrows of a single memory location
+pub trait AliasingModel {
+    // Borrow of a new allocation or of a pointer whose parent is not known
+    fn new_ref(&mut self, tag: Tag, permission: Permission);
+
+    // Reborrow of derived_from with the permission of the new pointer
+    fn grant(&mut self, derived_from: Tag, tag: Tag, permission: Permission);
+
+    // Copy of a raw pointer, it has the same permissions as the original
+    fn share(&mut self, from: Tag, to: Tag);
+
+    // Use of the tag, false if the tag does not have the access
+    fn access(&mut self, access: Access, tag: Tag) -> bool;
+
+    fn read(&mut self, tag: Tag) {
+        self.access(Access::Read, tag);
+    }
+
+    fn write(&mut self, tag: Tag) {
+        self.access(Access::Write, tag);
+    }
+
+    // The borrows of the tag can not be invalidated until the end of the analyzed call
+    fn protect(&mut self, tag: Tag);
+
+    // The address of the tag was cast to an integer, accesses through Tag::Untagged may use its borrows
+    fn expose(&mut self, tag: Tag);
+
+    fn is_live(&self, tag: Tag) -> bool;
+
+    // Merge the borrows of another control flow path, the borrows of tags in keep survive if they are valid in either path
+    fn join_keeping(&mut self, other: &Self, keep: &HashSet<Tag>);
+
+    // Accesses without permission, borrows invalidated and borrows created since the last call
+    fn take_violations(&mut self) -> Vec<Violation>;
+    fn take_invalidations(&mut self) -> Vec<Invalidation>;
+    fn take_creations(&mut self) -> Vec<Creation>;
+}
+
+// Aliasing model selected from the command line
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AliasingModelKind {
+    StackedBorrows,
+    TreeBorrows,
+}
+
+impl Default for AliasingModelKind {
+    fn default() -> Self {
+        AliasingModelKind::StackedBorrows
+    }
+}
+
+impl FromStr for AliasingModelKind {
+    type Err = String;
+
+    fn from_str(model: &str) -> Result<Self, Self::Err> {
+        match model {
+            "stacked-borrows" => Ok(AliasingModelKind::StackedBorrows),
+            "tree-borrows" => Ok(AliasingModelKind::TreeBorrows),
+            other => Err(format!("Unknown aliasing model `{}`, expected `stacked-borrows` or `tree-borrows`", other)),
+        }
+    }
+}
+
+impl AliasingModelKind {
+    pub fn new_borrows(self) -> Borrows {
+        match self {
+            AliasingModelKind::StackedBorrows => Borrows::Stack(Stack::new()),
+            AliasingModelKind::TreeBorrows => Borrows::Tree(Tree::new()),
+        }
+    }
+}
+
+// Borrows of a location under the selected model
+#[derive(Clone, PartialEq)]
+pub enum Borrows {
+    Stack(Stack),
+    Tree(Tree),
+}
+
+impl fmt::Debug for Borrows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Borrows::Stack(stack) => stack.fmt(f),
+            Borrows::Tree(tree) => tree.fmt(f),
+        }
+    }
+}
+
+impl AliasingModel for Borrows {
+    fn new_ref(&mut self, tag: Tag, permission: Permission) {
+        match self {
+            Borrows::Stack(stack) => stack.new_ref(tag, permission),
+            Borrows::Tree(tree) => tree.new_ref(tag, permission),
+        }
+    }
+
+    fn grant(&mut self, derived_from: Tag, tag: Tag, permission: Permission) {
+        match self {
+            Borrows::Stack(stack) => stack.grant(derived_from, tag, permission),
+            Borrows::Tree(tree) => tree.grant(derived_from, tag, permission),
+        }
+    }
+
+    fn share(&mut self, from: Tag, to: Tag) {
+        match self {
+            Borrows::Stack(stack) => stack.share(from, to),
+            Borrows::Tree(tree) => tree.share(from, to),
+        }
+    }
+
+    fn access(&mut self, access: Access, tag: Tag) -> bool {
+        match self {
+            Borrows::Stack(stack) => stack.access(access, tag),
+            Borrows::Tree(tree) => tree.access(access, tag),
+        }
+    }
+
+    fn protect(&mut self, tag: Tag) {
+        match self {
+            Borrows::Stack(stack) => stack.protect(tag),
+            Borrows::Tree(tree) => tree.protect(tag),
+        }
+    }
+
+    fn expose(&mut self, tag: Tag) {
+        match self {
+            Borrows::Stack(stack) => stack.expose(tag),
+            Borrows::Tree(tree) => tree.expose(tag),
+        }
+    }
+
+    fn is_live(&self, tag: Tag) -> bool {
+        match self {
+            Borrows::Stack(stack) => stack.is_live(tag),
+            Borrows::Tree(tree) => tree.is_live(tag),
+        }
+    }
+
+    // Both paths use the same model
+    fn join_keeping(&mut self, other: &Self, keep: &HashSet<Tag>) {
+        match (self, other) {
+            (Borrows::Stack(stack), Borrows::Stack(other)) => stack.join_keeping(other, keep),
+            (Borrows::Tree(tree), Borrows::Tree(other)) => tree.join_keeping(other, keep),
+            _ => unreachable!("borrows of different aliasing models"),
+        }
+    }
+
+    fn take_violations(&mut self) -> Vec<Violation> {
+        match self {
+            Borrows::Stack(stack) => stack.take_violations(),
+            Borrows::Tree(tree) => tree.take_violations(),
+        }
+    }
+
+    fn take_invalidations(&mut self) -> Vec<Invalidation> {
+        match self {
+            Borrows::Stack(stack) => stack.take_invalidations(),
+            Borrows::Tree(tree) => tree.take_invalidations(),
+        }
+    }
+
+    fn take_creations(&mut self) -> Vec<Creation> {
+        match self {
+            Borrows::Stack(stack) => stack.take_creations(),
+            Borrows::Tree(tree) => tree.take_creations(),
+        }
+    }
+}
@@ -1,9 +1,57 @@
-use crate::{mir_visitor::body_visitor::MirVisitor, stacked_borrows::Tag};
+use crate::mir_visitor::body_visitor::MirVisitor;
+use crate::aliasing_model::AliasingModelKind;
+use crate::findings::{Finding, FindingKind, Severity};
 use petgraph::dot::{Config, Dot};
-use rustc_middle::ty::TyCtxt;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::{Body, Local};
+use rustc_middle::ty::{self, Instance, TyCtxt};
+use rustc_middle::ty::subst::InternalSubsts;
+use rustc_session::config::Options;
 
-pub fn analyze<'tcx>(tcx: TyCtxt, main_function_name: Option<String>) {
-    let entry_fn_id = match main_function_name {
+// Call sites kept in the context of a callee when no depth is given
+pub const DEFAULT_CONTEXT_DEPTH: usize = 1;
+
+#[derive(Clone)]
+pub struct AnalyzerConfig {
+    pub entry_function: Option<String>,
+    pub all_functions: bool, // Analyze every body owner instead of a single entry point
+    pub verbose: bool, // Print the analysis trace on stdout
+    pub context_depth: usize, // Call sites that tell apart analyses of the same callee, 0 shares one summary for all calls
+    pub strict: bool, // Report MIR the analysis does not model as findings instead of skipping it
+    pub emit_retag: bool, // Analyze the unoptimized MIR with Retag statements that Miri runs on
+    pub aliasing_model: AliasingModelKind, // Rules the borrows of each location are checked with
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        AnalyzerConfig {
+            entry_function: None,
+            all_functions: false,
+            verbose: false,
+            context_depth: DEFAULT_CONTEXT_DEPTH,
+            strict: false,
+            emit_retag: false,
+            aliasing_model: AliasingModelKind::default(),
+        }
+    }
+}
+
+// Compiler options that select the MIR of the crate being analyzed
+pub fn set_mir_options(options: &mut Options, config: &AnalyzerConfig) {
+    // No optimization removes borrows or the storage markers that end the lifetime of locals
+    options.debugging_opts.mir_opt_level = Some(0);
+    if config.emit_retag {
+        // Every reborrow gets a Retag
+        options.debugging_opts.mir_emit_retag = true;
+    }
+}
+
+pub fn analyze(tcx: TyCtxt, config: &AnalyzerConfig) -> Vec<Finding> {
+    if config.all_functions {
+        return analyze_crate(tcx, config);
+    }
+
+    let entry_fn_id = match &config.entry_function {
         Some(name) => {
             let mut def_id = None;
             for item in tcx.hir().items() {
@@ -14,39 +62,95 @@ pub fn analyze<'tcx>(tcx: TyCtxt, main_function_name: Option<String>) {
                     }
                 }
             }
-            match def_id {
-                Some(id) => id,
-                None => {
-                    println!("Function name not found, using default entry function");
-                    tcx.entry_fn(()).unwrap().0 // default
-                }
+            if def_id.is_none() {
+                traceln!(config, "Function name not found, using default entry function");
             }
+            def_id.or_else(|| tcx.entry_fn(()).map(|(id, _)| id))
         }
-        None => tcx.entry_fn(()).unwrap().0, // default
+        None => tcx.entry_fn(()).map(|(id, _)| id), // default
     };
 
-    if tcx.is_mir_available(entry_fn_id) {
-        let function_body = tcx.optimized_mir(entry_fn_id);
-        let mut visitor = MirVisitor::new(tcx, &function_body, Vec::new());
-        visitor.visit_body(function_body);
-
-        let alias_vec = visitor.alias_graph.aliasing_test();
-        for may_alias_var in alias_vec {
-            let var_name = visitor.get_variable_name(may_alias_var as u32);
-            println!("Variable {} may have aliasing", var_name);
-            if !visitor.stacked_borrows.is_live(Tag::Tagged(may_alias_var as u32)) {
-                println!("Variable {} could be dead", var_name);
+    match entry_fn_id {
+        Some(def_id) => analyze_function(tcx, def_id, config),
+        None => {
+            traceln!(config, "No entry function found, analyzing every function in the crate");
+            analyze_crate(tcx, config)
+        }
+    }
+}
+
+// Analyze every body owner with MIR (functions, methods, closures and constants)
+pub fn analyze_crate(tcx: TyCtxt, config: &AnalyzerConfig) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for local_def_id in tcx.hir().body_owners() {
+        let def_id = local_def_id.to_def_id();
+        if tcx.is_mir_available(def_id) {
+            // A callee is also analyzed from its callers, report each finding once
+            for finding in analyze_function(tcx, def_id, config) {
+                let reported = findings.iter().any(|other| {
+                    other.kind == finding.kind && other.span == finding.span && other.message == finding.message
+                });
+                if !reported {
+                    findings.push(finding);
+                }
             }
         }
+    }
+    findings
+}
+
+pub fn analyze_function(tcx: TyCtxt, def_id: DefId, config: &AnalyzerConfig) -> Vec<Finding> {
+    if !tcx.is_mir_available(def_id) {
+        return Vec::new();
+    }
+
+    let function_name = tcx.def_path_str(def_id);
+    traceln!(config, "\n==== Analyzing {} ====", function_name);
 
-        println!(
-            "{:?}",
-            Dot::with_attr_getters(
-                &visitor.alias_graph.graph,
-                &[Config::EdgeNoLabel,Config::NodeNoLabel],
-                &|_, er| String::new(),
-                &|_, (index, refer)| format!("label = \"{}\"", visitor.get_variable_name(*refer)),
-            )
+    let function_body = get_body(tcx, def_id);
+    let mut visitor = MirVisitor::new(tcx, function_body, Vec::new(), config.clone());
+    let instance = Instance::new(def_id, InternalSubsts::identity_for_item(tcx, def_id));
+    visitor.summaries.in_progress.push((instance, Vec::new()));
+    visitor.visit_body(function_body);
+
+    traceln!(config, "\n==== Report for {} ====", function_name);
+    visitor.record_may_alias();
+    for may_alias_place in std::mem::take(&mut visitor.may_alias) {
+        let var_name = visitor.get_place_name(&may_alias_place);
+        let span = match may_alias_place.local() {
+            Some(local) => function_body.local_decls[Local::from_u32(local)].source_info.span,
+            None => visitor.allocation_site(&may_alias_place).unwrap().span,
+        };
+        // Places of derived and macro generated code are not written by the user
+        if span.from_expansion() {
+            continue;
+        }
+        let mut finding = visitor.new_finding(
+            FindingKind::MayAlias,
+            Severity::Warning,
+            span,
+            format!("variable `{}` may have aliasing", var_name),
         );
+        if let Some(may_alias_var) = may_alias_place.local() {
+            finding.locals.push(may_alias_var);
+        }
+        visitor.add_finding(finding);
     }
+
+    traceln!(config,
+        "{:?}",
+        Dot::with_attr_getters(
+            &visitor.alias_graph.graph,
+            &[Config::EdgeNoLabel,Config::NodeNoLabel],
+            &|_, er| String::new(),
+            &|_, (index, refer)| format!("label = \"{}\"", visitor.get_place_name(refer)),
+        )
+    );
+
+    visitor.findings
+}
+
+// Constants and statics only have MIR for const evaluation, functions and closures use the optimized one
+pub fn get_body(tcx: TyCtxt, def_id: DefId) -> &Body {
+    tcx.instance_mir(ty::InstanceDef::Item(ty::WithOptConstParam::unknown(def_id)))
 }
@@ -13,20 +13,28 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use rustc_session::config;
 use cargo_metadata::{MetadataCommand};
 // use rustc_span::source_map;
-use static_alias_analyzer::analyzer::analyze;
+use static_alias_analyzer::analyzer::{analyze, set_mir_options, AnalyzerConfig};
+use static_alias_analyzer::cli::parse_args;
+use static_alias_analyzer::findings::emit_diagnostics;
+use static_alias_analyzer::report::{findings_to_json, OutputFormat};
+use static_alias_analyzer::sarif::findings_to_sarif;
 
 use std::path;
 use std::path::PathBuf;
+use std::process;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let main_name = if args.len() >= 3 {
-        Some((&args[2]).to_owned())
-    } else {
-        None
+    let mut options = match parse_args(std::env::args()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
     };
-    let config = create_compiler_config();
-    run_compiler(config, main_name);
+    options.analyzer_config.entry_function = options.positional.get(2).cloned();
+    let mut config = create_compiler_config(options.analyzer_config.verbose);
+    set_mir_options(&mut config.opts, &options.analyzer_config);
+    run_compiler(config, options.analyzer_config, options.format);
 }
 
 fn compile_time_sysroot() -> Option<String> {
@@ -40,7 +48,7 @@ fn compile_time_sysroot() -> Option<String> {
     })
 }
 
-fn create_compiler_config() -> rustc_interface::Config {
+fn create_compiler_config(verbose: bool) -> rustc_interface::Config {
     let meta = MetadataCommand::new()
         .manifest_path("./Cargo.toml")
         .exec()
@@ -48,13 +56,17 @@ fn create_compiler_config() -> rustc_interface::Config {
 
     let filename = meta.packages[0].targets[0].src_path.clone();
     let directory = meta.workspace_root;
-    println!("{} {}", directory, filename);
+    if verbose {
+        println!("{} {}", directory, filename);
+    }
 
     // "/home/$username/.rustup/toolchains/nightly-2022-01-01-x86_64-unknown-linux-gnu"
     let sysroot = compile_time_sysroot().expect("Cannot find sysroot");
-    println!("{}", sysroot);
+    if verbose {
+        println!("{}", sysroot);
+    }
 
-    let config = rustc_interface::Config {
+    rustc_interface::Config {
         // Command line options
         opts: config::Options {
             maybe_sysroot: Some(path::PathBuf::from(sysroot)),
@@ -73,19 +85,22 @@ fn create_compiler_config() -> rustc_interface::Config {
         parse_sess_created: None, //Option<Box<dyn FnOnce(&mut ParseSess) + Send>>
         register_lints: None, // Option<Box<dyn Fn(&Session, &mut LintStore) + Send + Sync>>
         override_queries: None, // Option<fn(&Session, &mut ty::query::Providers<'_>, &mut ty::query::Providers<'_>)>
-        registry: registry::Registry::new(&rustc_error_codes::DIAGNOSTICS),
+        registry: registry::Registry::new(rustc_error_codes::DIAGNOSTICS),
         make_codegen_backend: None,
-    };
-
-    config
+    }
 }
 
-fn run_compiler(config: rustc_interface::Config, main_name: Option<String>) {
+fn run_compiler(config: rustc_interface::Config, analyzer_config: AnalyzerConfig, format: OutputFormat) {
     rustc_interface::run_compiler(config, |compiler| {
         compiler.enter(|queries| {
             // Analyze the program and inspect the types of definitions.
             queries.global_ctxt().unwrap().take().enter(|tcx| {
-                analyze(tcx, main_name);
+                let findings = analyze(tcx, &analyzer_config);
+                match format {
+                    OutputFormat::Text => emit_diagnostics(tcx, &findings),
+                    OutputFormat::Json => println!("{}", findings_to_json(tcx, &findings)),
+                    OutputFormat::Sarif => println!("{}", findings_to_sarif(tcx, &findings)),
+                }
             })
         });
     });
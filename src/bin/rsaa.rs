@@ -12,7 +12,11 @@ use rustc_errors::registry;
 use rustc_hash::{FxHashMap, FxHashSet};
 use rustc_session::config;
 // use rustc_span::source_map;
-use static_alias_analyzer::analyzer::analyze;
+use static_alias_analyzer::analyzer::{analyze, set_mir_options, AnalyzerConfig};
+use static_alias_analyzer::cli::{parse_args, OPTIONS};
+use static_alias_analyzer::findings::emit_diagnostics;
+use static_alias_analyzer::report::{findings_to_json, OutputFormat};
+use static_alias_analyzer::sarif::findings_to_sarif;
 
 use std::path;
 use std::path::PathBuf;
@@ -20,20 +24,24 @@ use std::process;
 use std::str;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut options = match parse_args(std::env::args()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    };
+    let args = &options.positional;
     if args.len() < 2 {
         println!("Please input a filename. Example usage");
-        println!("cargo run ./examples/hello_world.rs {{main function name}}");
+        println!("cargo run ./examples/hello_world.rs {{main function name}} {}", OPTIONS);
         return;
     }
 
-    let config = create_compiler_config(&args[1]);
-    let main_name = if args.len() > 2 {
-        Some((&args[2]).to_owned())
-    } else {
-        None
-    };
-    run_compiler(config, main_name);
+    let mut config = create_compiler_config(&args[1]);
+    set_mir_options(&mut config.opts, &options.analyzer_config);
+    options.analyzer_config.entry_function = args.get(2).cloned();
+    run_compiler(config, options.analyzer_config, options.format);
 }
 
 fn create_compiler_config(filename: &str) -> rustc_interface::Config {
@@ -45,7 +53,7 @@ fn create_compiler_config(filename: &str) -> rustc_interface::Config {
 
     let sysroot = str::from_utf8(&out.stdout).unwrap().trim();
 
-    let config = rustc_interface::Config {
+    rustc_interface::Config {
         // Command line options
         opts: config::Options {
             maybe_sysroot: Some(path::PathBuf::from(sysroot)),
@@ -64,19 +72,22 @@ fn create_compiler_config(filename: &str) -> rustc_interface::Config {
         parse_sess_created: None, //Option<Box<dyn FnOnce(&mut ParseSess) + Send>>
         register_lints: None, // Option<Box<dyn Fn(&Session, &mut LintStore) + Send + Sync>>
         override_queries: None, // Option<fn(&Session, &mut ty::query::Providers<'_>, &mut ty::query::Providers<'_>)>
-        registry: registry::Registry::new(&rustc_error_codes::DIAGNOSTICS),
+        registry: registry::Registry::new(rustc_error_codes::DIAGNOSTICS),
         make_codegen_backend: None,
-    };
-
-    config
+    }
 }
 
-fn run_compiler(config: rustc_interface::Config, main_function_name: Option<String>) {
+fn run_compiler(config: rustc_interface::Config, analyzer_config: AnalyzerConfig, format: OutputFormat) {
     rustc_interface::run_compiler(config, |compiler| {
         compiler.enter(|queries| {
             // Analyze the program and inspect the types of definitions.
             queries.global_ctxt().unwrap().take().enter(|tcx| {
-                analyze(tcx, main_function_name);
+                let findings = analyze(tcx, &analyzer_config);
+                match format {
+                    OutputFormat::Text => emit_diagnostics(tcx, &findings),
+                    OutputFormat::Json => println!("{}", findings_to_json(tcx, &findings)),
+                    OutputFormat::Sarif => println!("{}", findings_to_sarif(tcx, &findings)),
+                }
             })
         });
     });
@@ -0,0 +1,56 @@
+use crate::analyzer::AnalyzerConfig;
+use crate::report::OutputFormat;
+
+// Options accepted by rsaa and cargo-rsaa, as shown in the usage text
+pub const OPTIONS: &str = "[--all] [--strict] [--retag] [--format text|json|sarif] [--model stacked-borrows|tree-borrows] [--context-depth N]";
+
+// Command line options shared by rsaa and cargo-rsaa
+pub struct CliOptions {
+    pub positional: Vec<String>,
+    pub analyzer_config: AnalyzerConfig,
+    pub format: OutputFormat,
+}
+
+pub fn parse_args(args: impl Iterator<Item = String>) -> Result<CliOptions, String> {
+    let mut positional = Vec::new();
+    let mut analyzer_config = AnalyzerConfig::default();
+    let mut format = OutputFormat::Text;
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => analyzer_config.all_functions = true,
+            "--strict" => analyzer_config.strict = true,
+            "--retag" => analyzer_config.emit_retag = true,
+            "--format" => {
+                let value = args.next().ok_or("Missing value for --format")?;
+                format = value.parse()?;
+            }
+            other if other.starts_with("--format=") => format = other["--format=".len()..].parse()?,
+            "--model" => {
+                let value = args.next().ok_or("Missing value for --model")?;
+                analyzer_config.aliasing_model = value.parse()?;
+            }
+            other if other.starts_with("--model=") => analyzer_config.aliasing_model = other["--model=".len()..].parse()?,
+            "--context-depth" => {
+                let value = args.next().ok_or("Missing value for --context-depth")?;
+                analyzer_config.context_depth = parse_depth(&value)?;
+            }
+            other if other.starts_with("--context-depth=") => {
+                analyzer_config.context_depth = parse_depth(&other["--context-depth=".len()..])?;
+            }
+            // A mistyped option would otherwise be taken as the entry function
+            other if other.starts_with('-') => return Err(format!("Unknown option `{}`\nOptions: {}", other, OPTIONS)),
+            _ => positional.push(arg),
+        }
+    }
+
+    // Only findings are printed in machine readable formats
+    analyzer_config.verbose = format == OutputFormat::Text;
+
+    Ok(CliOptions { positional, analyzer_config, format })
+}
+
+fn parse_depth(value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("Invalid context depth `{}`, expected a number", value))
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::Location;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::stacked_borrows::Tag;
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum FindingKind {
+    StackedBorrowsViolation, // A tag was used after being popped from the borrow stack
+    MutableArgumentsAlias, // A function was called with two mutable arguments that may alias
+    CastSize, // A cast to a type with a smaller layout
+    MayAlias, // A variable is pointed to by two or more variables
+    DanglingPointer, // A pointer to storage that may be dead is dereferenced or returned
+    DivisionByZero, // A division or remainder whose divisor may be zero
+    AlignmentIncrease, // A pointer cast to a pointee with a stricter alignment
+    ConstToMutCast, // A const pointer cast to a mut pointer to a larger or more aligned type
+    TransmuteSize, // A transmute that reads more bytes than the source has
+    InvalidTransmute, // A transmute into a type for which the value may be an invalid bit pattern
+    Unsupported, // MIR the analysis does not model, only reported in strict mode
+    ProtectorViolation, // An access popped the item of a reference argument while the call is running
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FindingKind::StackedBorrowsViolation => write!(f, "stacked-borrows-violation"),
+            FindingKind::MutableArgumentsAlias => write!(f, "mutable-arguments-alias"),
+            FindingKind::CastSize => write!(f, "cast-size"),
+            FindingKind::MayAlias => write!(f, "may-alias"),
+            FindingKind::DanglingPointer => write!(f, "dangling-pointer"),
+            FindingKind::DivisionByZero => write!(f, "division-by-zero"),
+            FindingKind::AlignmentIncrease => write!(f, "alignment-increase"),
+            FindingKind::ConstToMutCast => write!(f, "const-to-mut-cast"),
+            FindingKind::TransmuteSize => write!(f, "transmute-size"),
+            FindingKind::InvalidTransmute => write!(f, "invalid-transmute"),
+            FindingKind::Unsupported => write!(f, "unsupported"),
+            FindingKind::ProtectorViolation => write!(f, "protector-violation"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+// Secondary explanation of a finding, optionally pointing at another part of the code
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub function: DefId, // Function whose body contains the finding
+    pub location: Option<Location>, // None when the finding is not tied to a statement
+    pub span: Span,
+    pub tags: Vec<Tag>,
+    pub locals: Vec<u32>,
+    pub message: String,
+    pub notes: Vec<Note>,
+}
+
+impl Finding {
+    pub fn new(kind: FindingKind, severity: Severity, function: DefId, span: Span, message: String) -> Finding {
+        Finding {
+            kind,
+            severity,
+            function,
+            location: None,
+            span,
+            tags: Vec::new(),
+            locals: Vec::new(),
+            message,
+            notes: Vec::new(),
+        }
+    }
+
+    // Several events of a borrow history can give the same note, it is kept once
+    pub fn note(&mut self, message: String) {
+        self.add_note(Note { span: None, message });
+    }
+
+    pub fn span_note(&mut self, span: Span, message: String) {
+        self.add_note(Note { span: Some(span), message });
+    }
+
+    fn add_note(&mut self, note: Note) {
+        if !self.notes.contains(&note) {
+            self.notes.push(note);
+        }
+    }
+}
+
+// Emit every finding as a compiler diagnostic
+pub fn emit_diagnostics(tcx: TyCtxt, findings: &[Finding]) {
+    for finding in findings {
+        let mut diagnostic = match finding.severity {
+            Severity::Error => tcx.sess.struct_span_err(finding.span, &finding.message),
+            Severity::Warning => tcx.sess.struct_span_warn(finding.span, &finding.message),
+        };
+        for note in &finding.notes {
+            match note.span {
+                Some(span) => diagnostic.span_note(span, &note.message),
+                None => diagnostic.note(&note.message),
+            };
+        }
+        diagnostic.emit();
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::Location;
+use rustc_span::Span;
+
+// Kind of heap memory created by an allocation site
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeapKind {
+    Box,
+    VecBuffer,
+    Rc,
+    Arc,
+}
+
+impl HeapKind {
+    // Reference counted objects are only freed when the last owner is dropped
+    pub fn is_shared(&self) -> bool {
+        matches!(self, HeapKind::Rc | HeapKind::Arc)
+    }
+}
+
+impl fmt::Display for HeapKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeapKind::Box => write!(f, "Box"),
+            HeapKind::VecBuffer => write!(f, "Vec"),
+            HeapKind::Rc => write!(f, "Rc"),
+            HeapKind::Arc => write!(f, "Arc"),
+        }
+    }
+}
+
+// Call or box expression that creates a heap object, every execution shares the same abstract object
+#[derive(Copy, Clone, Debug)]
+pub struct AllocationSite {
+    pub kind: HeapKind,
+    pub function: DefId,
+    pub location: Location,
+    pub span: Span,
+}
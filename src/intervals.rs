@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use rustc_middle::mir::BinOp;
+
+use crate::places::PlacePath;
+
+// Range of values an integer may hold, both bounds included
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Interval {
+    pub lo: i128,
+    pub hi: i128,
+}
+
+impl fmt::Debug for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |value: i128| match value {
+            i128::MIN => String::from("-inf"),
+            i128::MAX => String::from("+inf"),
+            value => value.to_string(),
+        };
+        write!(f, "[{}, {}]", bound(self.lo), bound(self.hi))
+    }
+}
+
+impl Interval {
+    pub fn top() -> Interval {
+        Interval { lo: i128::MIN, hi: i128::MAX }
+    }
+
+    pub fn constant(value: i128) -> Interval {
+        Interval { lo: value, hi: value }
+    }
+
+    pub fn boolean() -> Interval {
+        Interval { lo: 0, hi: 1 }
+    }
+
+    pub fn is_top(&self) -> bool {
+        *self == Interval::top()
+    }
+
+    pub fn contains(&self, value: i128) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    #[must_use]
+    pub fn join(self, other: &Interval) -> Interval {
+        Interval { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    // None if no value is in both intervals
+    pub fn meet(&self, other: &Interval) -> Option<Interval> {
+        let interval = Interval { lo: self.lo.max(other.lo), hi: self.hi.min(other.hi) };
+        if interval.lo <= interval.hi { Some(interval) } else { None }
+    }
+
+    // Bounds that keep growing in a loop jump to infinity
+    #[must_use]
+    pub fn widen(self, next: &Interval) -> Interval {
+        Interval {
+            lo: if next.lo < self.lo { i128::MIN } else { self.lo },
+            hi: if next.hi > self.hi { i128::MAX } else { self.hi },
+        }
+    }
+
+    // Only a value at one of the bounds can be removed from an interval
+    pub fn exclude(&self, value: i128) -> Option<Interval> {
+        if *self == Interval::constant(value) {
+            None
+        } else if self.lo == value {
+            Some(Interval { lo: value + 1, hi: self.hi })
+        } else if self.hi == value {
+            Some(Interval { lo: self.lo, hi: value - 1 })
+        } else {
+            Some(*self)
+        }
+    }
+
+    // Result of an arithmetic operation on an integer type with the given range of values, any value
+    // if it may overflow or is not modeled
+    pub fn binary_op(op: BinOp, a: &Interval, b: &Interval, range: &Interval) -> Interval {
+        // Widened bounds stand for the bounds of the type
+        let (a, b) = match (a.meet(range), b.meet(range)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Interval::top(),
+        };
+        let bounds = match op {
+            BinOp::Add => [a.lo.checked_add(b.lo), a.hi.checked_add(b.hi)].to_vec(),
+            BinOp::Sub => [a.lo.checked_sub(b.hi), a.hi.checked_sub(b.lo)].to_vec(),
+            BinOp::Mul => [a.lo.checked_mul(b.lo), a.lo.checked_mul(b.hi), a.hi.checked_mul(b.lo), a.hi.checked_mul(b.hi)].to_vec(),
+            BinOp::Div if !b.contains(0) => [a.lo.checked_div(b.lo), a.lo.checked_div(b.hi), a.hi.checked_div(b.lo), a.hi.checked_div(b.hi)].to_vec(),
+            _ => return Interval::top(),
+        };
+        let result = match bounds.into_iter().collect::<Option<Vec<i128>>>() {
+            Some(bounds) if !a.is_top() && !b.is_top() => Interval {
+                lo: *bounds.iter().min().unwrap(),
+                hi: *bounds.iter().max().unwrap(),
+            },
+            _ => return Interval::top(),
+        };
+        if range.meet(&result) == Some(result) { result } else { Interval::top() }
+    }
+
+    // Truth of a comparison, [0, 1] if it depends on the values
+    pub fn compare(op: BinOp, a: &Interval, b: &Interval) -> Interval {
+        let always = match op {
+            BinOp::Eq => a.lo == a.hi && *a == *b,
+            BinOp::Ne => a.meet(b).is_none(),
+            BinOp::Lt => a.hi < b.lo,
+            BinOp::Le => a.hi <= b.lo,
+            BinOp::Gt => a.lo > b.hi,
+            BinOp::Ge => a.lo >= b.hi,
+            _ => false,
+        };
+        let never = match op {
+            BinOp::Eq => a.meet(b).is_none(),
+            BinOp::Ne => a.lo == a.hi && *a == *b,
+            BinOp::Lt => a.lo >= b.hi,
+            BinOp::Le => a.lo > b.hi,
+            BinOp::Gt => a.hi <= b.lo,
+            BinOp::Ge => a.hi < b.lo,
+            _ => false,
+        };
+        match (always, never) {
+            (true, _) => Interval::constant(1),
+            (_, true) => Interval::constant(0),
+            _ => Interval::boolean(),
+        }
+    }
+}
+
+// Boolean local holding the result of comparing a place with a constant
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    pub op: BinOp,
+    pub subject: PlacePath,
+    pub constant: i128,
+}
+
+impl Condition {
+    // Values of the subject for which the condition has the given truth, None if it only excludes the constant
+    pub fn subject_values(&self, truth: bool) -> Option<Interval> {
+        let op = if truth { self.op } else { negate(self.op) };
+        let c = self.constant;
+        match op {
+            BinOp::Eq => Some(Interval::constant(c)),
+            BinOp::Lt => Some(Interval { lo: i128::MIN, hi: c.saturating_sub(1) }),
+            BinOp::Le => Some(Interval { lo: i128::MIN, hi: c }),
+            BinOp::Gt => Some(Interval { lo: c.saturating_add(1), hi: i128::MAX }),
+            BinOp::Ge => Some(Interval { lo: c, hi: i128::MAX }),
+            _ => None,
+        }
+    }
+}
+
+fn negate(op: BinOp) -> BinOp {
+    match op {
+        BinOp::Eq => BinOp::Ne,
+        BinOp::Ne => BinOp::Eq,
+        BinOp::Lt => BinOp::Ge,
+        BinOp::Le => BinOp::Gt,
+        BinOp::Gt => BinOp::Le,
+        BinOp::Ge => BinOp::Lt,
+        other => other,
+    }
+}
+
+pub fn is_comparison(op: BinOp) -> bool {
+    matches!(op, BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge)
+}
+
+// The comparison with the operands swapped, c < x is x > c
+pub fn swap_operands(op: BinOp) -> BinOp {
+    match op {
+        BinOp::Lt => BinOp::Gt,
+        BinOp::Le => BinOp::Ge,
+        BinOp::Gt => BinOp::Lt,
+        BinOp::Ge => BinOp::Le,
+        other => other,
+    }
+}
+
+// Integer values of the places of a function, a place without an interval may hold any value
+#[derive(Clone, Default, PartialEq)]
+pub struct IntervalState {
+    values: HashMap<PlacePath, Interval>,
+    conditions: HashMap<PlacePath, Condition>,
+    copies: HashMap<PlacePath, PlacePath>, // Place assigned a copy of another place that was not changed since
+    excluded: HashMap<PlacePath, Vec<i128>>, // Values inside the interval of a place that a guard ruled out
+}
+
+impl fmt::Debug for IntervalState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.values.iter()).finish()
+    }
+}
+
+impl IntervalState {
+    pub fn get(&self, path: &PlacePath) -> Interval {
+        self.values.get(path).copied().unwrap_or_else(Interval::top)
+    }
+
+    pub fn condition(&self, path: &PlacePath) -> Option<&Condition> {
+        self.conditions.get(path)
+    }
+
+    // Assigning a place forgets everything known about it and its fields
+    pub fn assign(&mut self, path: &PlacePath, interval: Interval) {
+        self.values.retain(|place, _| !path.overlaps(place));
+        self.conditions.retain(|place, condition| !path.overlaps(place) && !path.overlaps(&condition.subject));
+        self.copies.retain(|place, source| !path.overlaps(place) && !path.overlaps(source));
+        self.excluded.retain(|place, _| !path.overlaps(place));
+        if !interval.is_top() {
+            self.values.insert(path.clone(), interval);
+        }
+    }
+
+    pub fn add_condition(&mut self, path: &PlacePath, condition: Condition) {
+        self.conditions.insert(path.clone(), condition);
+    }
+
+    pub fn add_copy(&mut self, path: &PlacePath, source: &PlacePath) {
+        self.copies.insert(path.clone(), source.clone());
+        if let Some(excluded) = self.excluded.get(source).cloned() {
+            self.excluded.insert(path.clone(), excluded);
+        }
+    }
+
+    // The place is known not to hold the value
+    pub fn excludes(&self, path: &PlacePath, value: i128) -> bool {
+        !self.get(path).contains(value) || self.excluded.get(path).map_or(false, |excluded| excluded.contains(&value))
+    }
+
+    pub fn copy_source(&self, path: &PlacePath) -> Option<&PlacePath> {
+        self.copies.get(path)
+    }
+
+    // Restrict the values of a place and of the place it is a copy of, None if no value is left
+    pub fn restrict(&mut self, path: &PlacePath, allowed: &Interval) -> Option<()> {
+        let mut places = vec![path.clone()];
+        places.extend(self.copies.get(path).cloned());
+        for place in places {
+            let interval = self.get(&place).meet(allowed)?;
+            if !interval.is_top() {
+                self.values.insert(place, interval);
+            }
+        }
+        Some(())
+    }
+
+    // A value that is not a bound of the interval is remembered apart
+    pub fn exclude(&mut self, path: &PlacePath, value: i128) -> Option<()> {
+        let mut places = vec![path.clone()];
+        places.extend(self.copies.get(path).cloned());
+        for place in places {
+            let interval = self.get(&place).exclude(value)?;
+            if interval.contains(value) {
+                let excluded = self.excluded.entry(place.clone()).or_default();
+                if !excluded.contains(&value) {
+                    excluded.push(value);
+                }
+            }
+            if !interval.is_top() {
+                self.values.insert(place, interval);
+            }
+        }
+        Some(())
+    }
+
+    // The place is known to hold a value different from zero (true) or zero (false)
+    pub fn refine(&mut self, path: &PlacePath, truth: bool) -> Option<()> {
+        if truth {
+            self.exclude(path, 0)?;
+        } else {
+            self.restrict(path, &Interval::constant(0))?;
+        }
+        let condition = match self.condition(path) {
+            Some(condition) => condition.clone(),
+            None => return Some(()),
+        };
+        match condition.subject_values(truth) {
+            Some(allowed) => self.restrict(&condition.subject, &allowed),
+            None => self.exclude(&condition.subject, condition.constant),
+        }
+    }
+
+    // After a join point a place is known only if it is known in both paths
+    pub fn join(&mut self, other: &IntervalState) {
+        self.values = self.values.iter()
+            .filter_map(|(place, interval)| other.values.get(place).map(|other| (place.clone(), interval.join(other))))
+            .filter(|(_, interval)| !interval.is_top())
+            .collect();
+        self.conditions.retain(|place, condition| other.conditions.get(place) == Some(condition));
+        self.copies.retain(|place, source| other.copies.get(place) == Some(source));
+        self.excluded = self.excluded.iter()
+            .filter_map(|(place, excluded)| {
+                let both: Vec<i128> = excluded.iter().copied().filter(|value| other.excludes(place, *value)).collect();
+                if both.is_empty() { None } else { Some((place.clone(), both)) }
+            })
+            .collect();
+    }
+
+    pub fn widen(&mut self, previous: &IntervalState) {
+        for (place, interval) in self.values.iter_mut() {
+            if let Some(previous) = previous.values.get(place) {
+                *interval = previous.widen(interval);
+            }
+        }
+        self.values.retain(|_, interval| !interval.is_top());
+    }
+}
@@ -8,20 +8,59 @@ extern crate rustc_driver;
 extern crate rustc_errors;
 extern crate rustc_hir;
 extern crate rustc_hir_pretty;
+extern crate rustc_index;
+extern crate rustc_infer;
 extern crate rustc_interface;
 extern crate rustc_middle;
+extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
+extern crate rustc_trait_selection;
 extern crate log;
 
+// Analysis trace, silenced when the output has to be machine readable
+macro_rules! trace {
+    ($config:expr, $($arg:tt)*) => {
+        if $config.verbose {
+            print!($($arg)*);
+        }
+    };
+}
+
+macro_rules! traceln {
+    ($config:expr, $($arg:tt)*) => {
+        if $config.verbose {
+            println!($($arg)*);
+        }
+    };
+}
+
 pub mod analyzer;
 pub mod utils;
+pub mod aliasing_model;
 pub mod stacked_borrows;
+pub mod tree_borrows;
 pub mod points_to;
+pub mod places;
+pub mod heap;
+pub mod intervals;
+pub mod summary;
+pub mod findings;
+pub mod report;
+pub mod sarif;
+pub mod cli;
 
 pub mod mir_visitor {
     pub mod block_visitor;
     pub mod body_visitor;
+    pub mod dataflow;
+    pub mod reporter;
     pub mod terminator_visitor;
+    pub mod call_resolver;
+    pub mod heap_visitor;
+    pub mod provenance_visitor;
+    pub mod liveness;
+    pub mod interval_visitor;
+    pub mod layout_checker;
     pub mod helper;
 }
\ No newline at end of file
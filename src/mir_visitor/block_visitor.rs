@@ -1,14 +1,18 @@
 use rustc_middle::mir::{BasicBlock, BasicBlockData, Statement, Location};
 use rustc_middle::mir::{Place, Rvalue};
-use rustc_middle::mir::StatementKind::{Assign, SetDiscriminant, StorageDead, StorageLive};
+use rustc_middle::mir::StatementKind::{*};
+use rustc_middle::mir::{CopyNonOverlapping as CopyNonOverlappingData, RetagKind};
 use rustc_middle::mir::Operand;
 use rustc_middle::mir::Rvalue::{*};
-use rustc_middle::mir::BorrowKind;
+use rustc_middle::mir::{AggregateKind, BorrowKind, CastKind, Mutability};
+use rustc_middle::ty::adjustment::PointerCast;
+use rustc_middle::ty::TyKind;
 use rustc_middle::mir::ConstantKind;
 use rustc_target::abi::VariantIdx;
-use rustc_middle::ty::{ParamEnv, ParamEnvAnd, TyKind};
 
 use crate::stacked_borrows::{*};
+use crate::places::PathElem;
+use crate::heap::HeapKind;
 use super::body_visitor::MirVisitor;
 
 // Visitor trait implementation
@@ -18,19 +22,23 @@ impl<'tcx> MirVisitor<'tcx> {
         block: BasicBlock,
         data: &BasicBlockData<'tcx>
     ) {
-        println!("Block {} {:#?} --Start\n", self.func_name, block);
+        traceln!(self.config, "Block {} {:#?} --Start\n", self.func_name, block);
         let mut location = block.start_location();
         // Visit each statement of the basic block
         for statement in &data.statements {
             self.visit_statement(statement, location);
+            self.report_stack_violations(location, statement.source_info.span);
+            self.report_dangling_accesses(location, statement.source_info.span);
             location = location.successor_within_block();
         }
 
         // Visit the basic block terminator if there is one
         if let Some(terminator) = &data.terminator {
             self.visit_terminator(terminator, location);
+            self.report_stack_violations(location, terminator.source_info.span);
+            self.report_dangling_accesses(location, terminator.source_info.span);
         }
-        println!("\nBlock {} {:#?} --End \n", self.func_name, block);
+        traceln!(self.config, "\nBlock {} {:#?} --End \n", self.func_name, block);
     }
 
     fn visit_statement(
@@ -41,23 +49,29 @@ impl<'tcx> MirVisitor<'tcx> {
         match &statement.kind {
             Assign(assignment_box) => {
                 let (place, rvalue) = &**assignment_box;
+                if !place.is_indirect() {
+                    self.tag_spans.insert(self.place_to_tag(place), statement.source_info.span);
+                    self.revive_storage(&self.place_to_path(place));
+                }
                 self.visit_assign(place, rvalue, location);
+                self.update_intervals(place, rvalue);
             },
             SetDiscriminant {
                 place,
                 variant_index,
             } => self.visit_set_discriminant(place, *variant_index),
-            StorageDead(local) | StorageLive(local) => self.visit_storage(*local),
-
-            other => println!("Statement Kind not recognized {:?}", other)
+            StorageDead(local) => self.storage_dead(local.as_u32(), statement.source_info.span),
+            StorageLive(local) => self.storage_live(local.as_u32()),
+            CopyNonOverlapping(copy) => self.visit_copy_nonoverlapping(copy, location),
+            Retag(retag_kind, place) => self.visit_retag(*retag_kind, place, location),
+            // Only seen by the borrow checker, nothing is accessed at runtime
+            FakeRead(_) | AscribeUserType(..) | Coverage(_) | Nop => {
+                traceln!(self.config, "{:?} has no effect", statement.kind);
+            }
+            LlvmInlineAsm(_) => self.report_unsupported(location, statement.source_info.span, String::from("llvm_asm! is not supported")),
         }
     }
 
-    fn visit_storage(&mut self, local: rustc_middle::mir::Local) {
-        let _variable = local.as_u32();
-        // self.alias_graph.constant(variable);
-    }
-
     fn visit_set_discriminant(
         &mut self,
         place: &Place<'tcx>,
@@ -66,6 +80,71 @@ impl<'tcx> MirVisitor<'tcx> {
         self.add_to_stack(place);
     }
 
+    // Reads count elements through src and writes them through dst
+    fn visit_copy_nonoverlapping(
+        &mut self,
+        copy: &CopyNonOverlappingData<'tcx>,
+        location: Location
+    ) {
+        self.visit_operand(&copy.count, location);
+        let span = self.body.source_info(location).span;
+        // A constant pointer has no tag in the borrow stacks, the access through it is not checked
+        let src = copy.src.place().map(|src| self.tcx.mk_place_deref(src));
+        match src {
+            Some(src) => self.visit_operand(&Operand::Copy(src), location),
+            None => self.report_unsupported(location, span, format!("the read through the constant source `{:?}` is not checked", copy.src)),
+        }
+        let dst = copy.dst.place().map(|dst| self.tcx.mk_place_deref(dst));
+        match dst {
+            Some(dst) => self.add_to_stack(&dst),
+            None => self.report_unsupported(location, span, format!("the write through the constant destination `{:?}` is not checked", copy.dst)),
+        }
+        if let (Some(src), Some(dst)) = (src, dst) {
+            self.alias_graph.points_to(&self.place_to_path(&dst), &self.place_to_path(&src));
+        }
+        traceln!(self.config, "{:#?} Copy {:?} to {:?}", location, copy.src, copy.dst);
+    }
+
+    // The reference or Box in the place gets a new item on top of the stack of its pointee
+    fn visit_retag(
+        &mut self,
+        retag_kind: RetagKind,
+        place: &Place<'tcx>,
+        location: Location,
+    ) {
+        let span = self.body.source_info(location).span;
+        if !place.projection.is_empty() {
+            self.report_unsupported(location, span, format!("the retag of the projection `{:?}` is not modeled", place));
+            return;
+        }
+        let tag = self.place_to_tag(place);
+        let pointee = match self.stacked_borrows.provenance.get(&tag) {
+            Some(pointee) => pointee.clone(),
+            // The item of an argument is pushed by the call, the retag only records where the argument points
+            None if self.body.args_iter().any(|arg| arg == place.local) => {
+                let pointee = self.place_location(&self.tcx.mk_place_deref(*place));
+                self.stacked_borrows.provenance.insert(tag, pointee);
+                return;
+            }
+            None => {
+                let message = format!("`{}` is retagged without a known pointee", self.get_variable_name(place.local.as_u32()));
+                self.report_unsupported(location, span, message);
+                return;
+            }
+        };
+        let ty = place.ty(&self.local_declarations, self.tcx).ty;
+        let permission = match (retag_kind, ty.kind()) {
+            // A two-phase mutable borrow is Unique in Stacked Borrows, Tree Borrows reserves it until its first write
+            (RetagKind::TwoPhase, _) => Permission::Unique,
+            (RetagKind::Raw, TyKind::RawPtr(type_and_mut)) if type_and_mut.mutbl == Mutability::Mut => Permission::SharedReadWrite,
+            (_, TyKind::Ref(_, _, Mutability::Mut)) => Permission::Unique,
+            _ if ty.is_box() => Permission::Unique,
+            _ => self.shared_permission(&self.tcx.mk_place_deref(*place)),
+        };
+        self.stacked_borrows.grant(&pointee, tag, tag, permission);
+        traceln!(self.config, "Retag {:?} {} {:?}", retag_kind, self.get_variable_name(place.local.as_u32()), permission);
+    }
+
     fn visit_assign(
         &mut self,
         place: &Place<'tcx>,
@@ -73,143 +152,209 @@ impl<'tcx> MirVisitor<'tcx> {
         location: Location
     ) {
         let variable = place.local.as_u32();
+        let path = self.place_to_path(place);
         let tag = self.place_to_tag(place);
-        let variable_name = self.get_variable_name(variable);
+        let variable_name = self.get_place_name(&path);
         let mut operand_name = String::new();
 
 
         match rvalue {
             // Create or mutate variable (x or *x)
             Use(operand) => {
-                print!("use ");
+                trace!(self.config, "use ");
                 self.visit_operand(operand, location);
                 self.add_to_stack(place);
+                self.copy_provenance(place, operand);
                 if !place.is_indirect() { // is not a (&x)
-                    self.alias_graph.constant(variable);
+                    self.alias_graph.constant(&path);
                 }
-                if let Operand::Move(_) = operand {
-                    let operand_u32 = self.operand_as_u32(operand);
-                    self.alias_graph.points_to(variable, operand_u32);
-                    operand_name = format!("ref {}", self.get_variable_name(operand_u32));
+                if let Operand::Move(operand_place) = operand {
+                    let operand_path = self.place_to_path(operand_place);
+                    self.alias_graph.points_to(&path, &operand_path);
+                    operand_name = format!("ref {}", self.get_place_name(&operand_path));
                 }
-                if let Operand::Copy(_) = operand {
-                    operand_name = format!("ref {}", self.get_variable_name(self.operand_as_u32(operand)));
+                if let Operand::Copy(operand_place) = operand {
+                    operand_name = format!("ref {}", self.get_place_name(&self.place_to_path(operand_place)));
+                }
+                if let Some(operand_path) = self.operand_to_path(operand) {
+                    let targets = self.fn_pointer_targets(&operand_path);
+                    self.add_fn_targets(&path, targets);
+                    let types = self.arg_types(&operand_path);
+                    if !types.is_empty() {
+                        self.arg_pointee_types.insert(path.clone(), types);
+                    }
                 }
 
             },
             // Reference (&x or &mut x)
-            Ref(_region, borrow_kind, place) => {
-                print!("ref ");
+            Ref(_region, borrow_kind, borrowed_place) => {
+                trace!(self.config, "ref ");
+                let borrowed_tag = self.place_to_tag(borrowed_place);
+                self.check_dangling(borrowed_place);
+                let location = self.place_location(borrowed_place);
                 match borrow_kind {
-                    BorrowKind::Shared | BorrowKind::Shallow => { // Inmutable reference
-                        self.stacked_borrows.read_value(self.place_to_tag(place));
-                        self.stacked_borrows.new_ref(tag, Permission::SharedReadOnly);
+                    BorrowKind::Shared => { // Inmutable reference
+                        self.stacked_borrows.grant(&location, borrowed_tag, tag, self.shared_permission(borrowed_place));
                     }
-                    BorrowKind::Mut {allow_two_phase_borrow} => {  // Mutable reference
-                        match allow_two_phase_borrow {
-                            true => {self.stacked_borrows.use_value(self.place_to_tag(place));}
-                            false => {self.stacked_borrows.read_value(self.place_to_tag(place));}
-                        }
-                        self.stacked_borrows.new_ref(tag, Permission::Unique);
+                    // Match guard borrow, only seen by the borrow checker, it does not access the place
+                    BorrowKind::Shallow => {
+                        self.stacked_borrows.new_ref(&location, tag, Permission::SharedReadOnly);
                     }
-                    _ => {  // Mutable reference
-                        self.stacked_borrows.use_value(self.place_to_tag(place));
-                        self.stacked_borrows.new_ref(tag, Permission::Unique);
+                    // Mutable reference, or the closure capture of a place behind a shared reference to a mutable one (&mut *x)
+                    BorrowKind::Mut { .. } | BorrowKind::Unique => {
+                        self.stacked_borrows.grant(&location, borrowed_tag, tag, Permission::Unique);
                     }
                 };
-                self.alias_graph.points_to(variable, place.local.as_u32());
-                operand_name = format!("ref {}", self.get_variable_name(place.local.as_u32()));
+                self.add_to_stack(place);
+                if place.projection.is_empty() {
+                    self.stacked_borrows.provenance.insert(tag, location);
+                }
+                let borrowed_path = self.place_to_path(borrowed_place);
+                self.alias_graph.borrow(&path, &borrowed_path);
+                operand_name = format!("ref {}", self.get_place_name(&borrowed_path));
             },
             // Create a raw pointer (&raw const x)
-            AddressOf(_mutability, place) => {
-                print!("raw ");
-                self.stacked_borrows.use_value(self.place_to_tag(place));
-                self.stacked_borrows.new_ref(tag, Permission::SharedReadWrite);
-                self.alias_graph.points_to(variable, place.local.as_u32());
-                operand_name = format!("ref {}", self.get_variable_name(place.local.as_u32()));
+            AddressOf(mutability, borrowed_place) => {
+                trace!(self.config, "raw ");
+                self.check_dangling(borrowed_place);
+                let location = self.place_location(borrowed_place);
+                let borrowed_tag = self.place_to_tag(borrowed_place);
+                match mutability {
+                    Mutability::Mut => {
+                        self.stacked_borrows.grant(&location, borrowed_tag, tag, Permission::SharedReadWrite);
+                    }
+                    // A const raw pointer can only write to the parts of the place inside an UnsafeCell
+                    Mutability::Not => {
+                        self.stacked_borrows.grant(&location, borrowed_tag, tag, self.shared_permission(borrowed_place));
+                    }
+                }
+                self.add_to_stack(place);
+                if place.projection.is_empty() {
+                    self.stacked_borrows.provenance.insert(tag, location);
+                }
+                let borrowed_path = self.place_to_path(borrowed_place);
+                self.alias_graph.borrow(&path, &borrowed_path);
+                operand_name = format!("ref {}", self.get_place_name(&borrowed_path));
             }
             // Creates an aggregate value, like a tuple or struct
             Aggregate(_kind,operands) => {
-                print!("agg ");
+                trace!(self.config, "agg ");
                 for operand in operands {
                     self.visit_operand(operand, location);
                 }
                 self.add_to_stack(place);
-                self.alias_graph.constant(variable);
-            },
-            // Check cast kind equals type - Same size of T - raw pointers
-            Cast(_cast_kind, operand, ty) => {
-                print!("kst ");
+                self.alias_graph.constant(&path);
 
-                let mut operand_ty = operand.ty(&self.local_declarations,self.tcx);
-                let mut cast_type = *ty;
-
-                if let TyKind::RawPtr(type_and_mut) = operand_ty.kind() {
-                    operand_ty = type_and_mut.ty;
-                }
-
-                if let TyKind::RawPtr(type_and_mut) = cast_type.kind() {
-                    cast_type = type_and_mut.ty;
-                }
-
-                if operand_ty.is_trivially_sized(self.tcx) && cast_type.is_trivially_sized(self.tcx) {
-                    let operand_query = ParamEnvAnd { param_env: ParamEnv::empty(), value: operand_ty };
-                    let ty_query = ParamEnvAnd { param_env: ParamEnv::empty(), value: cast_type };
-
-                    match (self.tcx.layout_of(operand_query), self.tcx.layout_of(ty_query)) {
-                        (Ok(operand_layout), Ok(cast_layout)) => {
-                            if operand_layout.size > cast_layout.size {
-                                println!("WARNING: Casting from a layout with {} bits to {} bits", operand_layout.size.bytes(), cast_layout.size.bytes());
-                                println!("from {:#?} to {:#?}", operand.ty(&self.local_declarations,self.tcx), ty);
-                            }
-                        },
-                        other => println!("Error while calculating cast type sizes"),
+                // Fields holding references point to the borrowed places, closures and generators point to their captures
+                for (field, operand) in operands.iter().enumerate() {
+                    if let Operand::Move(operand_place) = operand {
+                        let mut field_path = path.clone();
+                        field_path.projection.push(match **_kind {
+                            AggregateKind::Array(_) => PathElem::ConstantIndex(field as u64),
+                            _ => PathElem::Field(field as u32),
+                        });
+                        self.alias_graph.points_to(&field_path, &self.place_to_path(operand_place));
                     }
-
                 }
+            },
+            // Check the layouts of the cast types - raw pointers
+            Cast(cast_kind, operand, ty) => {
+                trace!(self.config, "kst ");
+
+                self.check_cast(place, cast_kind, operand, *ty, location);
 
                 self.visit_operand(operand, location);
                 self.add_to_stack(place);
-                self.alias_graph.constant(variable);
+                if !self.cast_provenance(place, operand, *ty) {
+                    self.copy_provenance(place, operand);
+                }
+                self.alias_graph.constant(&path);
+                match cast_kind {
+                    // The trait object keeps pointing to the concrete value
+                    CastKind::Pointer(PointerCast::Unsize) => {
+                        if let Some(operand_path) = self.operand_to_path(operand) {
+                            self.alias_graph.points_to(&path, &operand_path);
+                        }
+                    }
+                    CastKind::Pointer(PointerCast::ReifyFnPointer) | CastKind::Pointer(PointerCast::ClosureFnPointer(_)) => {
+                        let targets = self.fn_pointer_source(operand);
+                        self.add_fn_targets(&path, targets);
+                    }
+                    _ => {}
+                }
                 operand_name = format!("ref {}", self.get_variable_name(self.operand_as_u32(operand)));
             },
             BinaryOp(_op, box_tuple) | CheckedBinaryOp(_op, box_tuple) => {
-                print!("bin ");
+                trace!(self.config, "bin ");
                 let (operand1, operand2) = *box_tuple.clone();
                 self.visit_operand(&operand2, location);
                 self.visit_operand(&operand1, location);
                 self.add_to_stack(place);
-                self.alias_graph.constant(variable);
+                self.alias_graph.constant(&path);
 
             },
             UnaryOp(unary, operand) => {
-                print!("un  ");
-                self.visit_operand(&operand, location);
+                trace!(self.config, "un  ");
+                self.visit_operand(operand, location);
                 self.add_to_stack(place);
-                self.alias_graph.constant(variable);
+                self.alias_graph.constant(&path);
             },
             // SizeOf(T) - AlignOf(T)
             NullaryOp(_null_op, _operand) => {
-                print!("nul ");
+                trace!(self.config, "nul ");
                 self.add_to_stack(place);
-                self.alias_graph.constant(variable);
+                self.alias_graph.constant(&path);
             },
             ShallowInitBox(operand, _ty) => {
-                print!("box ");
+                trace!(self.config, "box ");
                 self.add_to_stack(place);
-                self.alias_graph.points_to(variable, self.operand_as_u32(operand));
+                let span = self.body.source_info(location).span;
+                let object = self.heap_object(HeapKind::Box, location, span);
+                self.allocate_heap_object(place, object, &[]);
             },
-            Discriminant(_place) => {
-                print!("dsc ");
+            // Reads the discriminant or the length stored in the place
+            Discriminant(read_place) | Len(read_place) => {
+                trace!(self.config, "dsc ");
+                self.visit_operand(&Operand::Copy(*read_place), location);
                 self.add_to_stack(place);
-                self.alias_graph.constant(variable);
-
+                self.alias_graph.constant(&path);
+                let read_name = self.get_place_name(&self.place_to_path(read_place));
+                // The length of a slice is part of the pointer to it, reading it is approximated as a read of the slice
+                let read_ty = self.monomorphize(read_place.ty(&self.local_declarations, self.tcx).ty);
+                if matches!(rvalue, Len(_)) && matches!(read_ty.kind(), TyKind::Slice(_)) {
+                    let message = format!("the length of `{}` is read as a read of all of its elements", read_name);
+                    self.report_unsupported(location, self.body.source_info(location).span, message);
+                }
+                operand_name = format!("of {}", read_name);
+            }
+            // Array with copies of the operand ([x; N]), every element points where the operand does
+            Repeat(operand, _count) => {
+                trace!(self.config, "rep ");
+                self.visit_operand(operand, location);
+                self.add_to_stack(place);
+                self.alias_graph.constant(&path);
+                if let Some(operand_path) = self.operand_to_path(operand) {
+                    let mut element_path = path.clone();
+                    element_path.projection.push(PathElem::Index);
+                    self.alias_graph.points_to(&element_path, &operand_path);
+                    operand_name = format!("ref {}", self.get_place_name(&operand_path));
+                }
+                let message = format!("the elements of `{}` are approximated by a single element", variable_name);
+                self.report_unsupported(location, self.body.source_info(location).span, message);
+            }
+            // Pointer to a thread local static, its memory is not tracked
+            ThreadLocalRef(def_id) => {
+                trace!(self.config, "tls ");
+                self.add_to_stack(place);
+                self.alias_graph.constant(&path);
+                let message = format!("the memory of thread local `{}` is not tracked", self.tcx.def_path_str(*def_id));
+                self.report_unsupported(location, self.body.source_info(location).span, message);
+                operand_name = format!("ref {}", self.tcx.def_path_str(*def_id));
             }
-            other => println!("Rvalue kind not recognized {:?} ", other),
         }
 
         // println!("{:#?} Assign {} = {:?} {} | {:#?}", location, variable_name, rvalue, operand_name, self.stacked_borrows);
-        println!("{:#?} Assign {} = {:?} {}", location, variable_name, rvalue, operand_name);
+        traceln!(self.config, "{:#?} Assign {} = {:?} {}", location, variable_name, rvalue, operand_name);
     }
 
     pub fn visit_operand(
@@ -217,24 +362,28 @@ impl<'tcx> MirVisitor<'tcx> {
         operand: &Operand<'tcx>,
         location: Location
     ) {
+        if let Some(place) = operand.place() {
+            self.check_dangling(&place);
+        }
         match operand {
             Operand::Move(place) => {
                 // println!("M");
+                self.move_out(place);
                 if !place.is_indirect() { // is not a (&x)
-                    if self.is_raw_ptr(place) {
-                        self.stacked_borrows.use_raw(self.place_to_tag(place));
+                    let used_location = self.used_location(place);
+                    let ty = place.ty(&self.local_declarations, self.tcx).ty;
+                    // Shared references and const raw pointers can only be read through
+                    if ty.is_any_ptr() && !ty.is_mutable_ptr() {
+                        self.stacked_borrows.read(&used_location, self.place_to_tag(place));
                     } else {
-                        self.stacked_borrows.use_value(self.place_to_tag(place));
+                        self.stacked_borrows.write(&used_location, self.place_to_tag(place));
                     }
                 }
             }
             Operand::Copy(place) => {
                 // println!("C");
-                if self.is_raw_ptr(place) {
-                    self.stacked_borrows.read_raw(self.place_to_tag(place));
-                } else {
-                    self.stacked_borrows.read_value(self.place_to_tag(place));
-                }
+                let used_location = self.used_location(place);
+                self.stacked_borrows.read(&used_location, self.place_to_tag(place));
             }
             Operand::Constant(boxed_constant) => {
                 let constant = *boxed_constant.clone();
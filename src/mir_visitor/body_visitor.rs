@@ -1,35 +1,74 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use rustc_middle::mir::{Local, LocalDecl, LocalDecls, Body};
+use rustc_middle::mir::{Local, LocalDecl, LocalDecls, Location, Body};
 use rustc_middle::mir::{Operand, VarDebugInfoContents};
-use rustc_middle::ty::{TyCtxt};
+use rustc_middle::ty::{Instance, Ty, TyCtxt};
+use rustc_span::Span;
 
 use crate::stacked_borrows::{*};
 use crate::points_to::PointsToGraph;
+use crate::intervals::IntervalState;
+use crate::places::PlacePath;
+use crate::findings::Finding;
+use crate::summary::{CallString, SummaryCache};
+use crate::analyzer::AnalyzerConfig;
 
 pub struct MirVisitor<'tcx> {
     pub tcx: TyCtxt<'tcx>,
+    pub config: AnalyzerConfig,
     pub body: &'tcx Body<'tcx>,
     pub args: Vec<Operand<'tcx>>,
     pub func_name: String,
     pub local_declarations: LocalDecls<'tcx>,
     pub variable_names: HashMap<u32, String>,
-    pub stacked_borrows: Stack,
+    pub stacked_borrows: BorrowStacks,
     pub alias_graph: PointsToGraph,
+    pub dead_storage: HashMap<PlacePath, Span>, // Locals and heap objects that may be dead, and where they died
+    pub dangling_accesses: Vec<(PlacePath, PlacePath)>, // Pointers used while pointing to dead storage
+    pub moved_out: HashSet<PlacePath>, // Locals whose value was moved to another place or into a call
+    pub may_alias: Vec<PlacePath>, // Memory that had two or more live borrowers at some point
+    pub intervals: IntervalState,
+    pub fn_targets: HashMap<PlacePath, Vec<Instance<'tcx>>>, // Functions a function pointer may hold
+    pub arg_pointee_types: HashMap<PlacePath, Vec<Ty<'tcx>>>, // Types of the caller variables the arguments and their copies may point to
+    pub tag_spans: HashMap<Tag, Span>, // Where each tag was last created
+    pub borrow_history: HashMap<Tag, Vec<(Location, Span, BorrowEvent)>>, // Where the borrows of each tag were created and invalidated
+    pub findings: Vec<Finding>,
+    pub instance: Option<Instance<'tcx>>, // Monomorphized callee, None for the analyzed entry points
+    pub summaries: SummaryCache<'tcx>,
+    pub call_string: CallString, // Call sites that lead to this function
+    pub call_site: Option<Span>, // Call that is analyzed, None for the entry points
+    pub aliased_args: Vec<(u32, u32)>, // Arguments that may alias at the call site
 }
 
 // Basic Functions
 impl<'tcx> MirVisitor<'tcx> {
-    pub fn new(tcx: TyCtxt<'tcx>, body:&'tcx Body<'tcx>, args: Vec<Operand<'tcx>>) -> Self {
+    pub fn new(tcx: TyCtxt<'tcx>, body:&'tcx Body<'tcx>, args: Vec<Operand<'tcx>>, config: AnalyzerConfig) -> Self {
+        let stacked_borrows = BorrowStacks::new(config.aliasing_model);
         MirVisitor {
             tcx,
+            config,
             body,
             args,
             func_name: String::new(),
             local_declarations: LocalDecls::new(),
             variable_names: HashMap::new(),
-            stacked_borrows: Stack::new(),
-            alias_graph: PointsToGraph::new()
+            stacked_borrows,
+            alias_graph: PointsToGraph::new(),
+            dead_storage: HashMap::new(),
+            dangling_accesses: Vec::new(),
+            moved_out: HashSet::new(),
+            may_alias: Vec::new(),
+            intervals: IntervalState::default(),
+            fn_targets: HashMap::new(),
+            arg_pointee_types: HashMap::new(),
+            tag_spans: HashMap::new(),
+            borrow_history: HashMap::new(),
+            findings: Vec::new(),
+            instance: None,
+            summaries: SummaryCache::default(),
+            call_string: Vec::new(),
+            call_site: None,
+            aliased_args: Vec::new(),
         }
     }
 }
@@ -39,7 +78,7 @@ impl<'tcx> MirVisitor<'tcx> {
     pub fn visit_body(&mut self, body: &Body<'tcx>) {
         let name = MirVisitor::<'tcx>::get_body_func_name(body);
         self.func_name = name;
-        println!("\n{} body -- Start\n", self.func_name);
+        traceln!(self.config, "\n{} body -- Start\n", self.func_name);
 
         // Create a hashmap with variable real names
         for variable in &self.body.var_debug_info {
@@ -58,14 +97,12 @@ impl<'tcx> MirVisitor<'tcx> {
 
         // Visit arguments and local declarations
         self.push_args();
+        self.record_argument_history();
         self.local_declarations = body.local_decls.clone();
 
-        // Visit function basic blocks
-        let basic_blocks = body.basic_blocks().clone();
-        for (block, data) in basic_blocks.into_iter_enumerated() {
-            self.visit_basic_block_data(block, &data);
-        }
-        println!("{} body -- End", self.func_name);
+        // Visit function basic blocks following the control flow graph
+        self.visit_blocks(body);
+        traceln!(self.config, "{} body -- End", self.func_name);
     }
 
     // Function Declarations
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::iter;
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::{Local, Operand};
+use rustc_middle::ty::{self, Instance, InstanceDef, Ty, TyKind};
+use rustc_middle::ty::subst::SubstsRef;
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_trait_selection::infer::InferCtxtExt;
+
+use crate::places::{PathElem, PlacePath};
+use crate::stacked_borrows::Tag;
+use super::body_visitor::MirVisitor;
+
+// Resolution of the functions that a call terminator may execute
+impl<'tcx> MirVisitor<'tcx> {
+    pub fn call_targets(&self, func: &Operand<'tcx>, args: &[Operand<'tcx>]) -> Vec<Instance<'tcx>> {
+        match func {
+            Operand::Constant(constant) => {
+                // Ignore if it's a macro
+                if constant.span.from_expansion() {
+                    return Vec::new();
+                }
+                let func_ty = constant.literal.ty();
+                traceln!(self.config, "const ty {:#?}", func_ty);
+                match func_ty.kind() {
+                    TyKind::FnDef(def_id, subs_ref) => self.resolve_call(*def_id, subs_ref, args),
+                    _ => Vec::new(),
+                }
+            }
+            // Function pointer stored in a variable
+            Operand::Move(place) | Operand::Copy(place) => {
+                let targets = self.fn_pointer_targets(&self.place_to_path(place));
+                if targets.is_empty() {
+                    traceln!(self.config, "unknown targets for the function pointer {}", self.get_place_name(&self.place_to_path(place)));
+                }
+                targets
+            }
+        }
+    }
+
+    // Call through a function pointer or a trait object
+    pub fn is_dynamic_call(&self, func: &Operand<'tcx>) -> bool {
+        match func {
+            Operand::Move(_) | Operand::Copy(_) => true,
+            Operand::Constant(constant) => match constant.literal.ty().kind() {
+                TyKind::FnDef(def_id, substs) => matches!(
+                    Instance::resolve(self.tcx, self.param_env(), *def_id, self.monomorphize(substs)),
+                    Ok(Some(Instance { def: InstanceDef::Virtual(..), .. }))
+                ),
+                _ => false,
+            },
+        }
+    }
+
+    // Concrete bodies called with the given substitutions, trait methods are resolved to their impl
+    pub fn resolve_call(&self, def_id: DefId, substs: SubstsRef<'tcx>, args: &[Operand<'tcx>]) -> Vec<Instance<'tcx>> {
+        let substs = self.monomorphize(substs);
+        match Instance::resolve(self.tcx, self.param_env(), def_id, substs) {
+            Ok(Some(instance)) => match instance.def {
+                InstanceDef::Item(item) if self.tcx.is_mir_available(item.did) => vec![instance],
+                InstanceDef::Virtual(method, _) => self.virtual_call_targets(method, substs, args),
+                // FnOnce call of a Fn or FnMut closure, the shim only moves the environment
+                InstanceDef::ClosureOnceShim { .. } => match instance.substs.type_at(0).kind() {
+                    TyKind::Closure(def_id, closure_substs) => vec![Instance::new(*def_id, closure_substs)],
+                    _ => Vec::new(),
+                },
+                _ => {
+                    traceln!(self.config, "no body to analyze for {}", instance);
+                    Vec::new()
+                }
+            },
+            _ => {
+                traceln!(self.config, "could not resolve {}", self.tcx.def_path_str_with_substs(def_id, substs));
+                Vec::new()
+            }
+        }
+    }
+
+    // Call through a trait object, the concrete types the receiver was unsized from are the candidates
+    fn virtual_call_targets(&self, method: DefId, substs: SubstsRef<'tcx>, args: &[Operand<'tcx>]) -> Vec<Instance<'tcx>> {
+        let trait_id = match self.tcx.trait_of_item(method) {
+            Some(trait_id) => trait_id,
+            None => return Vec::new(),
+        };
+
+        // Generic implementations are selected for the concrete type like any other
+        let trait_params = self.tcx.mk_substs(substs.iter().take(self.tcx.generics_of(trait_id).count()).skip(1));
+        let implements_trait = |ty: Ty<'tcx>| self.tcx.infer_ctxt().enter(|infcx| {
+            infcx.type_implements_trait(trait_id, ty, trait_params, ty::ParamEnv::reveal_all()).must_apply_modulo_regions()
+        });
+        let self_types: Vec<Ty<'tcx>> = match args.first().and_then(|receiver| self.operand_to_path(receiver)) {
+            Some(receiver) => self.pointee_types(&receiver).into_iter()
+                .filter(|ty| ty.is_closure() || implements_trait(*ty))
+                .collect(),
+            None => Vec::new(),
+        };
+        if self_types.is_empty() {
+            traceln!(self.config, "receiver type of the call to {} unknown", self.tcx.def_path_str(method));
+        }
+
+        // Implementations without MIR are kept, the call reports them as not analyzed
+        let mut targets = Vec::new();
+        for self_ty in self_types {
+            let concrete_substs = self.tcx.mk_substs(iter::once(self_ty.into()).chain(substs.iter().skip(1)));
+            if let Ok(Some(instance)) = Instance::resolve(self.tcx, ty::ParamEnv::reveal_all(), method, concrete_substs) {
+                if matches!(instance.def, InstanceDef::Item(_)) && !targets.contains(&instance) {
+                    targets.push(instance);
+                }
+            }
+        }
+        let names: Vec<String> = targets.iter().map(|target| target.to_string()).collect();
+        traceln!(self.config, "dynamic call to {} may execute {:?}", self.tcx.def_path_str(method), names);
+        targets
+    }
+
+    // Types of the variables reachable from a pointer that are not pointers themselves, and of the
+    // caller variables an argument points to
+    pub fn pointee_types(&self, pointer: &PlacePath) -> Vec<Ty<'tcx>> {
+        let mut places = self.alias_graph.reachable_places(pointer);
+        // Copies of a reference are not in the graph, the location it points into is kept with its tag
+        let locations: Vec<PlacePath> = places.iter()
+            .filter_map(|place| place.local().filter(|_| place.is_local()))
+            .filter_map(|local| self.stacked_borrows.provenance.get(&Tag::Tagged(local)).cloned())
+            .collect();
+        places.extend(locations);
+
+        let mut types = self.arg_types(pointer);
+        for place in places {
+            let local = match place.local() {
+                Some(local) if place.is_local() => local,
+                _ => continue, // The type of heap objects is not known
+            };
+            let ty = self.monomorphize(self.local_declarations[Local::from_u32(local)].ty);
+            // The type of a pointer to a sized value, such as a promoted constant, is the type of its pointee
+            let ty = match ty.builtin_deref(true) {
+                Some(pointee) if !pointee.ty.is_trait() => pointee.ty,
+                _ => ty,
+            };
+            if !ty.is_any_ptr() && !ty.is_trait() && !types.contains(&ty) {
+                types.push(ty);
+            }
+        }
+        types
+    }
+
+    // Types of the caller variables that an argument reachable from the pointer points to
+    pub fn arg_types(&self, pointer: &PlacePath) -> Vec<Ty<'tcx>> {
+        let mut types = Vec::new();
+        for place in self.alias_graph.reachable_places(pointer) {
+            let arguments = iter::once(place.clone()).chain(place.pointer());
+            for ty in arguments.filter_map(|argument| self.arg_pointee_types.get(&argument)).flatten() {
+                if !types.contains(ty) {
+                    types.push(*ty);
+                }
+            }
+        }
+        types
+    }
+
+    // Functions stored in the place or in any place it points to
+    pub fn fn_pointer_targets(&self, path: &PlacePath) -> Vec<Instance<'tcx>> {
+        let mut targets: Vec<Instance<'tcx>> = self.fn_targets.get(path).cloned().unwrap_or_default();
+        for reachable in self.alias_graph.reachable_places(path) {
+            for target in self.fn_targets.get(&reachable).into_iter().flatten() {
+                if !targets.contains(target) {
+                    targets.push(*target);
+                }
+            }
+        }
+        targets
+    }
+
+    // Function item or closure converted to a function pointer
+    pub fn fn_pointer_source(&self, operand: &Operand<'tcx>) -> Vec<Instance<'tcx>> {
+        let ty = self.monomorphize(operand.ty(&self.local_declarations, self.tcx));
+        match ty.kind() {
+            TyKind::FnDef(def_id, subs_ref) => self.resolve_call(*def_id, subs_ref, &[]),
+            TyKind::Closure(def_id, subs_ref) if self.tcx.is_mir_available(*def_id) => vec![Instance::new(*def_id, subs_ref)],
+            _ => Vec::new(),
+        }
+    }
+
+    // Caller place passed to each argument local of the callee
+    pub fn callee_arguments(&self, instance: Instance<'tcx>, func: &Operand<'tcx>, args: &[Operand<'tcx>]) -> HashMap<u32, PlacePath> {
+        let paths: Vec<Option<PlacePath>> = args.iter().map(|arg| self.operand_to_path(arg)).collect();
+        let mut callee_args = HashMap::new();
+        if !self.tcx.is_closure(instance.def_id()) {
+            for (index, path) in paths.into_iter().enumerate() {
+                if let Some(path) = path {
+                    callee_args.insert(index as u32 + 1, path);
+                }
+            }
+        } else if func.place().is_some() {
+            // A closure called through a function pointer receives its environment before the arguments
+            for (index, path) in paths.into_iter().enumerate() {
+                if let Some(path) = path {
+                    callee_args.insert(index as u32 + 2, path);
+                }
+            }
+        } else {
+            // The Fn traits pass the environment and a tuple that the closure body receives untupled
+            if let Some(Some(environment)) = paths.get(0) {
+                callee_args.insert(1, environment.clone());
+            }
+            if let Some(Some(tuple)) = paths.get(1) {
+                let arg_count = self.tcx.instance_mir(instance.def).arg_count as u32;
+                for field in 0..arg_count.saturating_sub(1) {
+                    let mut path = tuple.clone();
+                    path.projection.push(PathElem::Field(field));
+                    callee_args.insert(field + 2, path);
+                }
+            }
+        }
+        callee_args
+    }
+
+    pub fn add_fn_targets(&mut self, path: &PlacePath, targets: Vec<Instance<'tcx>>) {
+        if targets.is_empty() {
+            return;
+        }
+        let known = self.fn_targets.entry(path.clone()).or_default();
+        for target in targets {
+            if !known.contains(&target) {
+                known.push(target);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{HashMap, VecDeque};
+
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::{BasicBlock, Body, Location, START_BLOCK};
+use rustc_middle::mir::terminator::TerminatorKind;
+use rustc_span::Span;
+
+use crate::stacked_borrows::BorrowStacks;
+use crate::points_to::PointsToGraph;
+use crate::places::PlacePath;
+use crate::intervals::IntervalState;
+use super::body_visitor::MirVisitor;
+
+// Upper bound of visits for a single block, guarantees termination of loops that never stabilize
+const MAX_BLOCK_VISITS: usize = 20;
+
+// Visits of a loop head after which the intervals of its entry state are widened
+const WIDENING_VISITS: usize = 2;
+
+// Analysis state at the entry or exit of a basic block
+#[derive(Clone, PartialEq)]
+pub struct BlockState {
+    pub stacked_borrows: BorrowStacks,
+    pub alias_graph: PointsToGraph,
+    pub dead_storage: HashMap<PlacePath, Span>,
+    pub intervals: IntervalState,
+}
+
+impl BlockState {
+    pub fn join(&mut self, other: &BlockState) {
+        self.stacked_borrows.join(&other.stacked_borrows);
+        self.alias_graph.join(&other.alias_graph);
+        // Storage is dead if it may have died in any path
+        for (path, span) in &other.dead_storage {
+            self.dead_storage.entry(path.clone()).or_insert(*span);
+        }
+        self.intervals.join(&other.intervals);
+    }
+}
+
+// Worklist dataflow over the control flow graph
+impl<'tcx> MirVisitor<'tcx> {
+    pub fn visit_blocks(&mut self, body: &Body<'tcx>) {
+        let basic_blocks = body.basic_blocks();
+        let mut entry_states: IndexVec<BasicBlock, Option<BlockState>> = IndexVec::from_elem(None, basic_blocks);
+        let mut visits: IndexVec<BasicBlock, usize> = IndexVec::from_elem(0, basic_blocks);
+        let mut return_state: Option<BlockState> = None;
+        let dominators = body.dominators();
+
+        entry_states[START_BLOCK] = Some(self.get_state());
+        let mut worklist = VecDeque::from(vec![START_BLOCK]);
+
+        while let Some(block) = worklist.pop_front() {
+            let data = &basic_blocks[block];
+            visits[block] += 1;
+
+            self.set_state(entry_states[block].clone().unwrap());
+            self.visit_basic_block_data(block, data);
+            let exit_state = self.get_state();
+
+            let terminator = data.terminator();
+            if let TerminatorKind::Return = terminator.kind {
+                match &mut return_state {
+                    Some(state) => state.join(&exit_state),
+                    None => return_state = Some(exit_state.clone()),
+                }
+            }
+
+            // Propagate the exit state to every successor, merging it at join points
+            for successor in terminator.successors() {
+                let mut edge_state = exit_state.clone();
+                if self.refine_edge(terminator, *successor, &mut edge_state.intervals).is_none() {
+                    traceln!(self.config, "{:?} is not reachable from {:?}", successor, block);
+                    continue;
+                }
+                let new_state = match &entry_states[*successor] {
+                    Some(old_state) => {
+                        let mut state = old_state.clone();
+                        state.join(&edge_state);
+                        // Only a back edge can make the values grow forever
+                        if visits[*successor] >= WIDENING_VISITS && dominators.is_dominated_by(block, *successor) {
+                            state.intervals.widen(&old_state.intervals);
+                        }
+                        state
+                    }
+                    None => edge_state,
+                };
+
+                if entry_states[*successor].as_ref() != Some(&new_state) {
+                    entry_states[*successor] = Some(new_state);
+                    if visits[*successor] >= MAX_BLOCK_VISITS {
+                        // The new entry state is not analyzed, findings that depend on it are missed
+                        let location = Location { block: *successor, statement_index: 0 };
+                        let span = basic_blocks[*successor].terminator().source_info.span;
+                        self.report_unsupported(location, span, format!("{:?} did not converge after {} visits", successor, MAX_BLOCK_VISITS));
+                    } else if !worklist.contains(successor) {
+                        worklist.push_back(*successor);
+                    }
+                }
+            }
+        }
+
+        // Leave the visitor with the state at the function exit
+        if let Some(state) = return_state {
+            self.set_state(state);
+        }
+    }
+
+    pub fn get_state(&self) -> BlockState {
+        BlockState {
+            stacked_borrows: self.stacked_borrows.clone(),
+            alias_graph: self.alias_graph.clone(),
+            dead_storage: self.dead_storage.clone(),
+            intervals: self.intervals.clone(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: BlockState) {
+        self.stacked_borrows = state.stacked_borrows;
+        self.alias_graph = state.alias_graph;
+        self.dead_storage = state.dead_storage;
+        self.intervals = state.intervals;
+    }
+}
@@ -0,0 +1,195 @@
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::{Location, Mutability, Operand, Place};
+use rustc_middle::ty::TyKind;
+use rustc_span::{sym, Span};
+
+use crate::heap::{AllocationSite, HeapKind};
+use crate::places::{PlaceBase, PlacePath};
+use crate::stacked_borrows::{Permission, Tag};
+use super::body_visitor::MirVisitor;
+
+// Associated functions that return a new heap object, their bodies are not analyzed
+const ALLOCATING_FUNCTIONS: [&str; 8] = ["new", "pin", "from", "with_capacity", "new_uninit", "new_zeroed", "default", "new_cyclic"];
+
+// Heap objects created by Box, Vec, Rc and Arc
+impl<'tcx> MirVisitor<'tcx> {
+    // Kind of heap object the called function allocates, if it is one of the known constructors
+    pub fn allocation_kind(&self, func: &Operand<'tcx>) -> Option<HeapKind> {
+        let (def_id, substs) = match func.constant()?.literal.ty().kind() {
+            TyKind::FnDef(def_id, substs) => (*def_id, *substs),
+            _ => return None,
+        };
+        let name = self.tcx.item_name(def_id);
+        if !ALLOCATING_FUNCTIONS.contains(&name.as_str()) {
+            return None;
+        }
+        // Trait constructors like From::from are called with the created type as Self
+        let self_ty = match self.tcx.trait_of_item(def_id) {
+            Some(_) => self.monomorphize(substs.type_at(0)),
+            None => self.tcx.type_of(self.tcx.impl_of_method(def_id)?),
+        };
+        self.heap_kind_of(self_ty.ty_adt_def()?.did)
+    }
+
+    fn heap_kind_of(&self, adt: DefId) -> Option<HeapKind> {
+        if Some(adt) == self.tcx.lang_items().owned_box() {
+            Some(HeapKind::Box)
+        } else if self.tcx.is_diagnostic_item(sym::Vec, adt) {
+            Some(HeapKind::VecBuffer)
+        } else if self.tcx.is_diagnostic_item(sym::Rc, adt) {
+            Some(HeapKind::Rc)
+        } else if self.tcx.is_diagnostic_item(sym::Arc, adt) {
+            Some(HeapKind::Arc)
+        } else {
+            None
+        }
+    }
+
+    // Number of the heap object of an allocation site, every execution of the site shares it
+    pub fn heap_object(&mut self, kind: HeapKind, location: Location, span: Span) -> PlacePath {
+        let function = self.body.source.def_id();
+        let sites = &mut self.summaries.allocation_sites;
+        let site = match sites.iter().position(|site| site.function == function && site.location == location) {
+            Some(site) => site,
+            None => {
+                sites.push(AllocationSite { kind, function, location, span });
+                sites.len() - 1
+            }
+        };
+        PlacePath::heap(site as u32)
+    }
+
+    pub fn allocation_site(&self, path: &PlacePath) -> Option<&AllocationSite> {
+        match path.base {
+            PlaceBase::Heap(site) => self.summaries.allocation_sites.get(site as usize),
+            PlaceBase::Local(_) => None,
+        }
+    }
+
+    // The owner of a new heap object points to it and is the only pointer with access
+    pub fn allocate_heap_object(&mut self, place: &Place<'tcx>, object: PlacePath, contents: &[Operand<'tcx>]) {
+        traceln!(self.config, "{} owns the new heap object {:?}", self.get_variable_name(place.local.as_u32()), object);
+        let path = self.place_to_path(place);
+        self.revive_storage(&object);
+        self.alias_graph.constant(&path);
+        self.alias_graph.points_to(&path, &object);
+
+        // Pointers moved into the object are stored in it
+        for operand in contents {
+            if let Some(operand_path) = self.operand_to_path(operand) {
+                self.alias_graph.points_to(&object, &operand_path);
+            }
+        }
+
+        if place.projection.is_empty() {
+            let tag = self.place_to_tag(place);
+            self.stacked_borrows.deallocate(&object);
+            self.stacked_borrows.new_ref(&object, tag, Permission::Unique);
+            self.stacked_borrows.provenance.insert(tag, object);
+        }
+    }
+
+    // A Vec method that returns a reference or a raw pointer, like `index_mut` or `as_mut_ptr`, points into
+    // the buffer of the vector the receiver points to and is derived from the owner of the buffer
+    pub fn vec_buffer_pointer(&mut self, args: &[Operand<'tcx>], destination: Option<Place<'tcx>>) {
+        let place = match destination {
+            Some(place) if place.projection.is_empty() => place,
+            _ => return,
+        };
+        let (owner, object) = match self.receiver_object(args) {
+            Some((owner, object, HeapKind::VecBuffer)) => (owner, object),
+            _ => return,
+        };
+        let permission = match self.monomorphize(place.ty(&self.local_declarations, self.tcx).ty).kind() {
+            TyKind::Ref(_, _, Mutability::Mut) => Permission::Unique,
+            TyKind::Ref(_, _, Mutability::Not) => Permission::SharedReadOnly,
+            TyKind::RawPtr(_) => Permission::SharedReadWrite,
+            _ => return,
+        };
+        traceln!(self.config, "{} points into {}", self.get_variable_name(place.local.as_u32()), self.get_place_name(&object));
+        let tag = self.place_to_tag(&place);
+        self.stacked_borrows.grant(&object, owner, tag, permission);
+        self.stacked_borrows.provenance.insert(tag, object.clone());
+        self.alias_graph.borrow(&self.place_to_path(&place), &object);
+    }
+
+    // A clone of an Rc or Arc is another owner of the object of the receiver
+    pub fn shared_owner_clone(&mut self, func: &Operand<'tcx>, args: &[Operand<'tcx>], destination: Option<Place<'tcx>>) {
+        let place = match destination {
+            Some(place) if place.projection.is_empty() => place,
+            _ => return,
+        };
+        let is_clone = match func.constant().map(|constant| constant.literal.ty().kind()) {
+            Some(TyKind::FnDef(def_id, _)) => {
+                self.tcx.trait_of_item(*def_id) == self.tcx.lang_items().clone_trait() && self.tcx.item_name(*def_id) == sym::clone
+            }
+            _ => false,
+        };
+        let (owner, object) = match self.receiver_object(args) {
+            Some((owner, object, kind)) if is_clone && kind.is_shared() => (owner, object),
+            _ => return,
+        };
+        traceln!(self.config, "{} also owns {}", self.get_variable_name(place.local.as_u32()), self.get_place_name(&object));
+        let tag = self.place_to_tag(&place);
+        self.stacked_borrows.grant(&object, owner, tag, Permission::SharedReadWrite);
+        self.stacked_borrows.provenance.insert(tag, object.clone());
+        self.alias_graph.points_to(&self.place_to_path(&place), &object);
+    }
+
+    // Tag of the owner the receiver of a method points to and the heap object it owns
+    fn receiver_object(&self, args: &[Operand<'tcx>]) -> Option<(Tag, PlacePath, HeapKind)> {
+        let receiver = match args.first() {
+            Some(Operand::Move(receiver) | Operand::Copy(receiver)) if receiver.projection.is_empty() => *receiver,
+            _ => return None,
+        };
+        let owner = Tag::Tagged(self.stacked_borrows.provenance.get(&self.place_to_tag(&receiver))?.local()?);
+        let object = self.stacked_borrows.provenance.get(&owner).filter(|object| object.is_heap())?.clone();
+        let kind = self.allocation_site(&object)?.kind;
+        Some((owner, object, kind))
+    }
+
+    // A value dropped by a callee frees the Box and Vec objects it points to, a value moved out of a
+    // borrowed owner only reaches the object through the provenance of the owner
+    pub fn free_owned_objects(&mut self, owner: &PlacePath, span: Span) {
+        let mut objects = self.alias_graph.pointees(owner);
+        objects.extend(self.owned_object(owner));
+        for object in objects {
+            if matches!(self.allocation_site(&object), Some(site) if !site.kind.is_shared()) {
+                traceln!(self.config, "callee frees {}", self.get_place_name(&object));
+                self.stacked_borrows.deallocate(&object);
+                self.kill_storage(object, span);
+            }
+        }
+    }
+
+    // Heap object at the end of the chain of locals a local was moved or derived from
+    fn owned_object(&self, owner: &PlacePath) -> Option<PlacePath> {
+        let mut visited = Vec::new();
+        let mut current = owner.clone();
+        while let Some(local) = current.local().filter(|_| current.is_local() && !visited.contains(&current)) {
+            let next = self.stacked_borrows.provenance.get(&Tag::Tagged(local))?.clone();
+            visited.push(current);
+            current = next;
+        }
+        Some(current).filter(|object| object.is_heap() && object.is_local())
+    }
+
+    // Dropping the owner of a Box or Vec frees its heap object, shared objects may have other owners
+    pub fn drop_heap_object(&mut self, place: &Place<'tcx>) -> Option<PlacePath> {
+        if !place.projection.is_empty() {
+            return None;
+        }
+        let object = match self.stacked_borrows.provenance.get(&self.place_to_tag(place)) {
+            Some(object) if object.is_heap() && object.is_local() => object.clone(),
+            _ => return None,
+        };
+        match self.allocation_site(&object) {
+            Some(site) if !site.kind.is_shared() => {
+                traceln!(self.config, "drop frees {}", self.get_place_name(&object));
+                self.stacked_borrows.deallocate(&object);
+                Some(object)
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use rustc_middle::mir::{Place, Body};
+use rustc_middle::mir::{Place, Body, Local, ProjectionElem};
 use rustc_middle::mir::Operand;
 use rustc_middle::mir::Mutability::Mut;
+use rustc_middle::ty::{ParamEnv, TypeFoldable};
 use std::fmt::Write as FmtWrite;
 
 // use crate::utils::print_mir;
 use crate::stacked_borrows::{*};
+use crate::places::{PathElem, PlaceBase, PlacePath};
 use super::body_visitor::MirVisitor;
 
 impl<'tcx> MirVisitor<'tcx> {
@@ -13,19 +15,93 @@ impl<'tcx> MirVisitor<'tcx> {
         Tag::Tagged(place.local.as_u32())
     }
 
+    // Memory location denoted by a place, a leading deref is resolved with the provenance of the pointer
+    pub fn place_location(&self, place: &Place) -> PlacePath {
+        let path = self.place_to_path(place);
+        if path.projection.first() == Some(&PathElem::Deref) {
+            if let Some(pointee) = self.stacked_borrows.provenance.get(&self.place_to_tag(place)) {
+                let mut location = pointee.clone();
+                location.projection.extend(path.projection.iter().skip(1));
+                return location;
+            }
+        }
+        path
+    }
+
+    // Using a pointer checks its tag on the location it points into
+    pub fn used_location(&self, place: &Place) -> PlacePath {
+        if place.projection.is_empty() {
+            if let Some(pointee) = self.stacked_borrows.provenance.get(&self.place_to_tag(place)) {
+                return pointee.clone();
+            }
+        }
+        self.place_location(place)
+    }
+
     pub fn add_to_stack(&mut self, place: &Place) {
         let tag = self.place_to_tag(place);
+        let location = self.place_location(place);
+        self.check_dangling(place);
         if !place.is_indirect() { // is not a (&x)
-            let variable_decl = self.local_declarations.get(place.local).unwrap();
-            let is_mutable = variable_decl.mutability == Mut;
-            let is_mut_ref = variable_decl.ty.is_mutable_ptr();
-            if is_mutable || is_mut_ref {
-                self.stacked_borrows.new_ref(tag, Permission::Unique);
-            } else {
-                self.stacked_borrows.new_ref(tag, Permission::SharedReadOnly);
+            if place.projection.is_empty() {
+                self.stacked_borrows.forget_pointer(tag);
+            }
+            // The storage of a local is only written through the local, whether it is mut or not
+            self.stacked_borrows.allocate(place.local.as_u32(), tag, Permission::Unique);
+            self.stacked_borrows.new_ref(&location, tag, Permission::Unique);
+        }
+        self.stacked_borrows.write(&location, tag);
+    }
+
+    // The assigned pointer points into the same location as the operand it is copied from
+    pub fn copy_provenance(&mut self, place: &Place, operand: &Operand) {
+        if place.is_indirect() || !place.projection.is_empty() {
+            return;
+        }
+        if let Operand::Move(operand_place) | Operand::Copy(operand_place) = operand {
+            if !operand_place.projection.is_empty() {
+                return;
+            }
+            let source_tag = self.place_to_tag(operand_place);
+            let tag = self.place_to_tag(place);
+            // A copy of a wildcard pointer is a wildcard pointer, a copy of an exposed address holds the same address
+            if self.stacked_borrows.wildcards.contains(&source_tag) && self.is_raw_ptr(place) {
+                self.stacked_borrows.wildcards.insert(tag);
             }
+            if let Some(address) = self.stacked_borrows.addresses.get(&source_tag).cloned() {
+                self.stacked_borrows.addresses.insert(tag, address);
+            }
+            if let Some(pointee) = self.stacked_borrows.provenance.get(&source_tag).cloned() {
+                // A raw pointer copy has the same permissions as the original, a reference is derived from it
+                if self.is_raw_ptr(place) {
+                    self.stacked_borrows.share(&pointee, source_tag, tag);
+                } else {
+                    self.stacked_borrows.grant(&pointee, source_tag, tag, self.pointer_permission(place));
+                }
+                self.stacked_borrows.provenance.insert(tag, pointee);
+            }
+        }
+    }
+
+    pub fn pointer_permission(&self, place: &Place) -> Permission {
+        let variable_decl = self.local_declarations.get(place.local).unwrap();
+        if variable_decl.ty.is_unsafe_ptr() {
+            Permission::SharedReadWrite
+        } else if variable_decl.ty.is_mutable_ptr() || variable_decl.ty.is_box() {
+            Permission::Unique
+        } else {
+            Permission::SharedReadOnly
+        }
+    }
+
+    // Shared pointers can write to the parts of the pointee inside an UnsafeCell
+    pub fn shared_permission(&self, place: &Place<'tcx>) -> Permission {
+        let ty = self.monomorphize(place.ty(&self.local_declarations, self.tcx).ty);
+        if ty.is_freeze(self.tcx.at(self.body.span), self.param_env()) {
+            Permission::SharedReadOnly
+        } else {
+            Permission::SharedReadWrite
         }
-        self.stacked_borrows.use_value(tag);
     }
 
     pub fn is_raw_ptr(&self, place: &Place) -> bool {
@@ -34,15 +110,62 @@ impl<'tcx> MirVisitor<'tcx> {
     }
 
     pub fn push_args(&mut self) {
-        let mut index = 1;
-        for _arg in &self.args {
-            self.stacked_borrows.new_ref(Tag::Tagged(index), Permission::Unique);
-            self.alias_graph.constant(index);
-            index += 1;
+        for arg in self.body.args_iter() {
+            let index = arg.as_u32();
+            let path = PlacePath::from_local(index);
+            self.stacked_borrows.new_ref(&path, Tag::Tagged(index), Permission::Unique);
+            // The pointee of a reference argument can not be invalidated before the function returns
+            if self.body.local_decls[arg].ty.is_ref() {
+                self.stacked_borrows.protect(&path, Tag::Tagged(index));
+            }
+            // A raw pointer argument shares its pointee with the raw pointers it was copied from
+            if self.body.local_decls[arg].ty.is_unsafe_ptr() {
+                let pointee = PlacePath { base: PlaceBase::Local(index), projection: vec![PathElem::Deref] };
+                self.stacked_borrows.new_ref(&pointee, Tag::Tagged(index), Permission::SharedReadWrite);
+            }
+            self.alias_graph.constant(&path);
+        }
+
+        // Arguments that alias at the call site point into the same location
+        for (first, second) in self.aliased_args.clone() {
+            let is_raw = |local: u32| self.body.local_decls[Local::from_u32(local)].ty.is_unsafe_ptr();
+            // A raw pointer that is still valid after the entry of the call is below the reference it aliases
+            let (base, derived) = if is_raw(second) && !is_raw(first) { (second, first) } else { (first, second) };
+            let pointee = PlacePath { base: PlaceBase::Local(base), projection: vec![PathElem::Deref] };
+            let tag = Tag::Tagged(derived);
+            // Two raw pointers are copies of each other
+            if is_raw(derived) {
+                self.stacked_borrows.share(&pointee, Tag::Tagged(base), tag);
+            } else {
+                self.stacked_borrows.new_ref(&pointee, tag, Permission::Unique);
+            }
+            if self.body.local_decls[Local::from_u32(derived)].ty.is_ref() {
+                self.stacked_borrows.protect(&pointee, tag);
+            }
+            self.stacked_borrows.provenance.insert(tag, pointee.clone());
+            self.alias_graph.points_to(&PlacePath::from_local(derived), &pointee);
         }
     }
 
     // Points-to analisis helper functions
+    pub fn place_to_path(&self, place: &Place) -> PlacePath {
+        let projection = place.projection.iter().map(|elem| match elem {
+            ProjectionElem::Deref => PathElem::Deref,
+            ProjectionElem::Field(field, _ty) => PathElem::Field(field.as_u32()),
+            ProjectionElem::ConstantIndex { offset, from_end: false, .. } => PathElem::ConstantIndex(offset),
+            ProjectionElem::Index(_) | ProjectionElem::ConstantIndex { .. } | ProjectionElem::Subslice { .. } => PathElem::Index,
+            ProjectionElem::Downcast(_name, variant) => PathElem::Downcast(variant.as_u32()),
+        }).collect();
+        PlacePath { base: PlaceBase::Local(place.local.as_u32()), projection }
+    }
+
+    pub fn operand_to_path(&self, operand: &Operand) -> Option<PlacePath> {
+        match operand {
+            Operand::Move(place) | Operand::Copy(place) => Some(self.place_to_path(place)),
+            Operand::Constant(_) => None,
+        }
+    }
+
     pub fn is_mutable(&self, operand: &mut Operand) -> bool {
         match operand {
             Operand::Move(place) | Operand::Copy(place) => {
@@ -66,6 +189,21 @@ impl<'tcx> MirVisitor<'tcx> {
         }
     }
 
+    // Generic parameters are replaced with the substitutions of the analyzed instance
+    pub fn monomorphize<T: TypeFoldable<'tcx> + Clone>(&self, value: T) -> T {
+        match self.instance {
+            Some(instance) => instance.subst_mir_and_normalize_erasing_regions(self.tcx, ParamEnv::reveal_all(), value),
+            None => self.tcx.normalize_erasing_regions(self.param_env(), value),
+        }
+    }
+
+    pub fn param_env(&self) -> ParamEnv<'tcx> {
+        match self.instance {
+            Some(_) => ParamEnv::reveal_all(),
+            None => self.tcx.param_env_reveal_all_normalized(self.body.source.def_id()),
+        }
+    }
+
     // Debugger help functions
     pub fn get_variable_name(&self, place: u32) -> String {
         let name = match self.variable_names.get(&place) {
@@ -76,11 +214,26 @@ impl<'tcx> MirVisitor<'tcx> {
         name
     }
 
+    pub fn get_place_name(&self, path: &PlacePath) -> String {
+        let base_name = match path.base {
+            PlaceBase::Local(local) => self.get_variable_name(local),
+            PlaceBase::Heap(site) => match self.summaries.allocation_sites.get(site as usize) {
+                Some(allocation) => format!("{}#{}", allocation.kind, site),
+                None => format!("heap#{}", site),
+            },
+        };
+        path.to_string_with(&base_name)
+    }
+
     pub fn get_body_func_name(body: &Body) -> String {
         let mut out = String::new();
         write!(&mut out, "{:?}", body.source.instance.def_id()).unwrap();
-        let mut name: String = String::from(out.split("::").collect::<Vec<&str>>()[1]);
-        name = name[0..1].to_uppercase() + &name[1..name.len()-1];
-        name
+        // The debug output of the DefId ends with the name of the item and a parenthesis
+        let segment = out.split("::").last().unwrap();
+        let mut chars = segment.strip_suffix(')').unwrap_or(segment).chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,243 @@
+use std::convert::TryFrom;
+
+use rustc_middle::mir::{AssertKind, BasicBlock, BinOp, Location, Operand, Place, Rvalue, Terminator, UnOp};
+use rustc_middle::mir::terminator::TerminatorKind;
+use rustc_middle::ty::{ParamEnvAnd, Ty};
+use rustc_span::Span;
+
+use crate::findings::{FindingKind, Severity};
+use crate::intervals::{is_comparison, swap_operands, Condition, Interval, IntervalState};
+use crate::places::{PathElem, PlacePath};
+use super::body_visitor::MirVisitor;
+
+// Constant propagation of integer values and division by zero checks
+impl<'tcx> MirVisitor<'tcx> {
+    // Value of an integer, bool or char constant
+    pub fn constant_value(&self, operand: &Operand<'tcx>) -> Option<i128> {
+        let literal = self.monomorphize(operand.constant()?.literal);
+        let ty = literal.ty();
+        if !ty.is_integral() && !ty.is_bool() && !ty.is_char() {
+            return None;
+        }
+        let bits = literal.try_eval_bits(self.tcx, self.param_env(), ty)?;
+        self.bits_to_value(bits, ty)
+    }
+
+    pub fn bits_to_value(&self, bits: u128, ty: Ty<'tcx>) -> Option<i128> {
+        if ty.is_signed() {
+            let size = self.tcx.layout_of(ParamEnvAnd { param_env: self.param_env(), value: ty }).ok()?.size;
+            Some(size.sign_extend(bits) as i128)
+        } else {
+            i128::try_from(bits).ok()
+        }
+    }
+
+    // Values of every integer type, casts that may not fit lose the interval
+    pub fn type_range(&self, ty: Ty<'tcx>) -> Interval {
+        if ty.is_bool() {
+            return Interval::boolean();
+        }
+        let size = match self.tcx.layout_of(ParamEnvAnd { param_env: self.param_env(), value: ty }) {
+            Ok(layout) if ty.is_integral() || ty.is_char() => layout.size,
+            _ => return Interval::top(),
+        };
+        let bits = size.bits() as u32;
+        match (ty.is_signed(), bits) {
+            (_, 128) | (false, 127) => Interval::top(),
+            (true, bits) => Interval { lo: -(1i128 << (bits - 1)), hi: (1i128 << (bits - 1)) - 1 },
+            (false, bits) => Interval { lo: 0, hi: (1i128 << bits) - 1 },
+        }
+    }
+
+    // A place whose address is taken may be changed through a pointer
+    fn is_address_taken(&self, path: &PlacePath) -> bool {
+        (0..=path.projection.len()).any(|length| {
+            let prefix = PlacePath { base: path.base, projection: path.projection[..length].to_vec() };
+            self.alias_graph.is_borrowed(&prefix)
+        })
+    }
+
+    // Place tracked by the intervals, places behind a pointer are not
+    fn tracked_path(&self, place: &Place) -> Option<PlacePath> {
+        let path = self.place_to_path(place);
+        if path.projection.contains(&PathElem::Deref) || self.is_address_taken(&path) {
+            return None;
+        }
+        Some(path)
+    }
+
+    pub fn operand_interval(&self, operand: &Operand<'tcx>) -> Interval {
+        match operand {
+            Operand::Constant(_) => self.constant_value(operand).map_or_else(Interval::top, Interval::constant),
+            Operand::Copy(place) | Operand::Move(place) => match self.tracked_path(place) {
+                Some(path) => self.intervals.get(&path),
+                None => Interval::top(),
+            },
+        }
+    }
+
+    pub fn update_intervals(&mut self, place: &Place<'tcx>, rvalue: &Rvalue<'tcx>) {
+        let path = match self.tracked_path(place) {
+            Some(path) => path,
+            None => return,
+        };
+        match rvalue {
+            Rvalue::Use(operand) => {
+                let interval = self.operand_interval(operand);
+                let source = operand.place().and_then(|source| self.tracked_path(&source));
+                let condition = source.as_ref().and_then(|source| self.intervals.condition(source).cloned());
+                self.intervals.assign(&path, interval);
+                if let Some(source) = source {
+                    self.intervals.add_copy(&path, &source);
+                }
+                if let Some(condition) = condition {
+                    self.intervals.add_condition(&path, condition);
+                }
+            }
+            Rvalue::BinaryOp(op, operands) | Rvalue::CheckedBinaryOp(op, operands) => {
+                let (a, b) = &**operands;
+                let (a_interval, b_interval) = (self.operand_interval(a), self.operand_interval(b));
+                let range = self.type_range(self.monomorphize(a.ty(&self.local_declarations, self.tcx)));
+                if is_comparison(*op) {
+                    let condition = match (a.place(), self.constant_value(b), self.constant_value(a), b.place()) {
+                        (Some(subject), Some(constant), ..) => self.tracked_path(&subject).map(|subject| Condition { op: *op, subject, constant }),
+                        (_, _, Some(constant), Some(subject)) => self.tracked_path(&subject).map(|subject| Condition { op: swap_operands(*op), subject, constant }),
+                        _ => None,
+                    };
+                    // A guard may have ruled out the constant without changing the interval of the subject
+                    let truth = match &condition {
+                        Some(condition) if self.intervals.excludes(&condition.subject, condition.constant) => match condition.op {
+                            BinOp::Eq => Interval::constant(0),
+                            BinOp::Ne => Interval::constant(1),
+                            _ => Interval::compare(*op, &a_interval, &b_interval),
+                        },
+                        _ => Interval::compare(*op, &a_interval, &b_interval),
+                    };
+                    self.intervals.assign(&path, truth);
+                    if let Some(condition) = condition {
+                        self.intervals.add_condition(&path, condition);
+                    }
+                } else if let Rvalue::CheckedBinaryOp(..) = rvalue {
+                    // The result is a tuple of the value and the overflow flag
+                    let mut value = path.clone();
+                    value.projection.push(PathElem::Field(0));
+                    self.intervals.assign(&path, Interval::top());
+                    self.intervals.assign(&value, Interval::binary_op(*op, &a_interval, &b_interval, &range));
+                } else {
+                    self.intervals.assign(&path, Interval::binary_op(*op, &a_interval, &b_interval, &range));
+                }
+            }
+            Rvalue::UnaryOp(UnOp::Neg, operand) => {
+                let interval = self.operand_interval(operand);
+                let negated = match (interval.lo.checked_neg(), interval.hi.checked_neg()) {
+                    (Some(hi), Some(lo)) if !interval.is_top() => Interval { lo, hi },
+                    _ => Interval::top(),
+                };
+                self.intervals.assign(&path, negated);
+            }
+            Rvalue::UnaryOp(UnOp::Not, operand) if operand.ty(&self.local_declarations, self.tcx).is_bool() => {
+                let interval = self.operand_interval(operand);
+                let negated = match interval.lo == interval.hi {
+                    true => Interval::constant(1 - interval.lo),
+                    false => Interval::boolean(),
+                };
+                self.intervals.assign(&path, negated);
+            }
+            Rvalue::Cast(_, operand, ty) => {
+                let interval = self.operand_interval(operand);
+                let range = self.type_range(self.monomorphize(*ty));
+                let fits = range.meet(&interval) == Some(interval);
+                self.intervals.assign(&path, if fits { interval } else { Interval::top() });
+            }
+            _ => self.intervals.assign(&path, Interval::top()),
+        }
+    }
+
+    // Values of the result of a call or other assignment that is not modeled
+    pub fn forget_interval(&mut self, place: &Place<'tcx>) {
+        if let Some(path) = self.tracked_path(place) {
+            self.intervals.assign(&path, Interval::top());
+        }
+    }
+
+    // Values known on the edge to successor, None if the edge can not be taken
+    pub fn refine_edge(&self, terminator: &Terminator<'tcx>, successor: BasicBlock, intervals: &mut IntervalState) -> Option<()> {
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr, switch_ty, targets } => {
+                let path = match discr.place().and_then(|place| self.tracked_path(&place)) {
+                    Some(path) => path,
+                    None => return Some(()),
+                };
+                let switch_ty = self.monomorphize(*switch_ty);
+                let explicit: Vec<(i128, BasicBlock)> = targets.iter()
+                    .filter_map(|(bits, target)| self.bits_to_value(bits, switch_ty).map(|value| (value, target)))
+                    .collect();
+                if explicit.len() != targets.iter().count() {
+                    return Some(());
+                }
+                let values: Vec<i128> = explicit.iter().filter(|(_, target)| *target == successor).map(|(value, _)| *value).collect();
+                let is_otherwise = targets.otherwise() == successor;
+
+                match (values.as_slice(), is_otherwise) {
+                    ([value], false) if switch_ty.is_bool() => intervals.refine(&path, *value != 0),
+                    ([value], false) => intervals.restrict(&path, &Interval::constant(*value)),
+                    ([], true) if switch_ty.is_bool() && explicit.len() == 1 => intervals.refine(&path, explicit[0].0 == 0),
+                    ([], true) => {
+                        for (value, _) in explicit {
+                            intervals.exclude(&path, value)?;
+                        }
+                        Some(())
+                    }
+                    _ => Some(()),
+                }
+            }
+            // Execution only continues if the condition has the expected value
+            TerminatorKind::Assert { cond, expected, target, .. } if *target == successor => {
+                match cond.place().and_then(|place| self.tracked_path(&place)) {
+                    Some(path) => intervals.refine(&path, *expected),
+                    None => Some(()),
+                }
+            }
+            _ => Some(()),
+        }
+    }
+
+    // The assert of a division fails if the divisor is zero, its condition compares the divisor with zero
+    pub fn check_divisor(&mut self, cond: &Operand<'tcx>, expected: bool, msg: &AssertKind<Operand<'tcx>>, location: Location, span: Span) {
+        let operation = match msg {
+            AssertKind::DivisionByZero(_) => "divide",
+            AssertKind::RemainderByZero(_) => "calculate the remainder",
+            _ => return,
+        };
+        let fails = if expected { 0 } else { 1 };
+        let cond_interval = self.operand_interval(cond);
+        if !cond_interval.contains(fails) {
+            return;
+        }
+        let divisor = cond.place()
+            .and_then(|place| self.tracked_path(&place))
+            .and_then(|path| self.intervals.condition(&path).cloned())
+            .filter(|condition| condition.op == BinOp::Eq && condition.constant == 0)
+            .map(|condition| condition.subject);
+        let divisor_interval = divisor.as_ref().map(|divisor| self.intervals.get(divisor));
+
+        // A divisor about which nothing is known may be zero too
+        let (severity, message) = if cond_interval == Interval::constant(fails) {
+            (Severity::Error, format!("attempt to {} by zero", operation))
+        } else {
+            (Severity::Warning, format!("attempt to {} by a divisor that may be zero", operation))
+        };
+        let mut finding = self.new_finding(FindingKind::DivisionByZero, severity, span, message);
+        finding.location = Some(location);
+
+        // Temporaries are named after the variable they copy
+        if let (Some(divisor), Some(interval)) = (divisor, divisor_interval) {
+            let source = self.intervals.copy_source(&divisor).cloned().unwrap_or(divisor);
+            match interval.is_top() {
+                true => finding.note(format!("nothing is known about the value of `{}`", self.get_place_name(&source))),
+                false => finding.note(format!("`{}` may be any value in {:?}", self.get_place_name(&source), interval)),
+            }
+        }
+        self.add_finding(finding);
+    }
+}
@@ -0,0 +1,189 @@
+use rustc_middle::mir::{CastKind, Location, Operand, Place};
+use rustc_middle::mir::Mutability;
+use rustc_middle::ty::{ParamEnvAnd, Ty, TyKind};
+use rustc_middle::ty::layout::TyAndLayout;
+use rustc_span::{sym, Span};
+
+use crate::findings::{FindingKind, Severity};
+use crate::intervals::Interval;
+use super::body_visitor::MirVisitor;
+
+// Highest code point, values above it and surrogates are not chars
+const MAX_CHAR: i128 = 0x10FFFF;
+
+// Size and alignment of the values of casts and transmutes
+impl<'tcx> MirVisitor<'tcx> {
+    fn layout(&self, ty: Ty<'tcx>) -> Option<TyAndLayout<'tcx>> {
+        if !ty.is_trivially_sized(self.tcx) {
+            return None;
+        }
+        self.tcx.layout_of(ParamEnvAnd { param_env: self.param_env(), value: ty }).ok()
+    }
+
+    // Pointee of a raw pointer or reference and whether it can be written through
+    fn pointee(ty: Ty<'tcx>) -> Option<(Ty<'tcx>, Mutability)> {
+        match ty.kind() {
+            TyKind::RawPtr(type_and_mut) => Some((type_and_mut.ty, type_and_mut.mutbl)),
+            TyKind::Ref(_, pointee, mutability) => Some((*pointee, *mutability)),
+            _ => None,
+        }
+    }
+
+    pub fn check_cast(&mut self, place: &Place<'tcx>, cast_kind: &CastKind, operand: &Operand<'tcx>, ty: Ty<'tcx>, location: Location) {
+        let span = self.body.source_info(location).span;
+        let operand_ty = self.monomorphize(operand.ty(&self.local_declarations, self.tcx));
+        let cast_ty = self.monomorphize(ty);
+        let locals = vec![place.local.as_u32(), self.operand_as_u32(operand)];
+
+        // Pointer casts compare what both sides point to, other casts compare the values themselves
+        let (from, to) = match (Self::pointee(operand_ty), Self::pointee(cast_ty)) {
+            (Some((from, _)), Some((to, _))) => (from, to),
+            _ => (operand_ty, cast_ty),
+        };
+        match (self.layout(from), self.layout(to)) {
+            (Some(operand_layout), Some(cast_layout)) => {
+                if operand_layout.size > cast_layout.size {
+                    let mut finding = self.new_finding(
+                        FindingKind::CastSize,
+                        Severity::Warning,
+                        span,
+                        format!("casting from a layout with {} bytes to {} bytes", operand_layout.size.bytes(), cast_layout.size.bytes()),
+                    );
+                    finding.location = Some(location);
+                    finding.locals = locals.clone();
+                    finding.note(format!("cast from `{}` to `{}`", operand_ty, cast_ty));
+                    self.add_finding(finding);
+                }
+            }
+            _ => traceln!(self.config, "Error while calculating cast type sizes"),
+        }
+
+        if *cast_kind != CastKind::Misc {
+            return;
+        }
+        let ((from, from_mutability), (to, to_mutability)) = match (Self::pointee(operand_ty), Self::pointee(cast_ty)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return,
+        };
+        let (from_layout, to_layout) = match (self.layout(from), self.layout(to)) {
+            (Some(from_layout), Some(to_layout)) => (from_layout, to_layout),
+            _ => return,
+        };
+        let (from_align, to_align) = (from_layout.align.abi.bytes(), to_layout.align.abi.bytes());
+
+        // A read-only pointer turned into a writable one to a different type
+        if from_mutability == Mutability::Not && to_mutability == Mutability::Mut && from != to
+            && (to_layout.size > from_layout.size || to_align > from_align) {
+            let mut finding = self.new_finding(
+                FindingKind::ConstToMutCast,
+                Severity::Warning,
+                span,
+                format!("casting `{}` to `{}` with a larger or more aligned pointee", operand_ty, cast_ty),
+            );
+            finding.location = Some(location);
+            finding.locals = locals;
+            finding.note(format!(
+                "`{}` has {} bytes aligned to {}, `{}` has {} bytes aligned to {}",
+                from, from_layout.size.bytes(), from_align, to, to_layout.size.bytes(), to_align,
+            ));
+            self.add_finding(finding);
+        } else if to_align > from_align {
+            let mut finding = self.new_finding(
+                FindingKind::AlignmentIncrease,
+                Severity::Warning,
+                span,
+                format!("casting `{}` to `{}` increases the alignment of the pointee from {} to {} bytes", operand_ty, cast_ty, from_align, to_align),
+            );
+            finding.location = Some(location);
+            finding.locals = locals;
+            finding.note(String::from("dereferencing the pointer is undefined behavior if the address is not aligned"));
+            self.add_finding(finding);
+        }
+    }
+
+    // Sizes and target values of mem::transmute and mem::transmute_copy calls
+    pub fn check_transmute(&mut self, func: &Operand<'tcx>, args: &[Operand<'tcx>], location: Location, span: Span) {
+        let (def_id, substs) = match func.constant().map(|constant| constant.literal.ty().kind()) {
+            Some(TyKind::FnDef(def_id, substs)) => (*def_id, self.monomorphize(*substs)),
+            _ => return,
+        };
+        let is_transmute = self.tcx.is_diagnostic_item(sym::transmute, def_id);
+        let is_transmute_copy = self.tcx.def_path_str(def_id).ends_with("mem::transmute_copy");
+        if !is_transmute && !is_transmute_copy {
+            return;
+        }
+        let (from, to) = (substs.type_at(0), substs.type_at(1));
+        traceln!(self.config, "transmute from {} to {}", from, to);
+
+        // transmute_copy reads the size of the target from the source
+        if is_transmute_copy {
+            if let (Some(from_layout), Some(to_layout)) = (self.layout(from), self.layout(to)) {
+                if to_layout.size > from_layout.size {
+                    let mut finding = self.new_finding(
+                        FindingKind::TransmuteSize,
+                        Severity::Error,
+                        span,
+                        format!("transmute_copy reads {} bytes from a value of {} bytes", to_layout.size.bytes(), from_layout.size.bytes()),
+                    );
+                    finding.location = Some(location);
+                    finding.note(format!("`{}` is larger than `{}`", to, from));
+                    self.add_finding(finding);
+                }
+            }
+            return;
+        }
+
+        let valid = match self.valid_values(to) {
+            Some(valid) if from != to => valid,
+            _ => return,
+        };
+        let value = match args.first() {
+            Some(arg) => self.operand_interval(arg).meet(&self.source_values(from)).unwrap_or_else(Interval::top),
+            None => Interval::top(),
+        };
+        let (severity, message) = if valid.iter().any(|range| range.meet(&value) == Some(value)) {
+            return; // Every possible value is valid
+        } else if valid.iter().all(|range| range.meet(&value).is_none()) {
+            (Severity::Error, format!("transmuting an invalid value into `{}`", to))
+        } else {
+            (Severity::Warning, format!("transmuting `{}` into `{}` may produce an invalid value", from, to))
+        };
+        let mut finding = self.new_finding(FindingKind::InvalidTransmute, severity, span, message);
+        finding.location = Some(location);
+        if !value.is_top() {
+            finding.note(format!("the value may be any in {:?}", value));
+        }
+        let valid: Vec<String> = valid.iter().map(|range| format!("{:?}", range)).collect();
+        finding.note(format!("valid values of `{}` are {}", to, valid.join(" or ")));
+        self.add_finding(finding);
+    }
+
+    // Bit patterns that are valid values of the type, None if every pattern of its size is valid
+    fn valid_values(&self, ty: Ty<'tcx>) -> Option<Vec<Interval>> {
+        match ty.kind() {
+            TyKind::Bool => Some(vec![Interval::boolean()]),
+            TyKind::Char => Some(vec![Interval { lo: 0, hi: 0xD7FF }, Interval { lo: 0xE000, hi: MAX_CHAR }]),
+            TyKind::Adt(adt, _) if adt.is_enum() && adt.is_payloadfree() => {
+                let mut discriminants: Vec<Interval> = adt.discriminants(self.tcx)
+                    .filter_map(|(_, discr)| self.bits_to_value(discr.val, discr.ty))
+                    .map(Interval::constant)
+                    .collect();
+                discriminants.sort_by_key(|discr| discr.lo);
+                Some(discriminants)
+            }
+            // References and function pointers can not be null
+            TyKind::Ref(..) | TyKind::FnPtr(_) => Some(vec![Interval { lo: 1, hi: i128::MAX }]),
+            TyKind::Adt(adt, _) if adt.is_box() => Some(vec![Interval { lo: 1, hi: i128::MAX }]),
+            // Values of enums with fields are not modeled
+            _ => None,
+        }
+    }
+
+    // Values a type can hold when it is transmuted
+    fn source_values(&self, ty: Ty<'tcx>) -> Interval {
+        match self.valid_values(ty) {
+            Some(valid) if !valid.is_empty() => Interval { lo: valid[0].lo, hi: valid[valid.len() - 1].hi },
+            _ => self.type_range(ty),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use rustc_middle::mir::{Location, Place};
+use rustc_span::Span;
+
+use crate::findings::{FindingKind, Severity};
+use crate::places::{PathElem, PlacePath};
+use crate::stacked_borrows::{Permission, Tag};
+use super::body_visitor::MirVisitor;
+
+// Storage liveness of locals and heap objects, and uses of pointers to dead storage
+impl<'tcx> MirVisitor<'tcx> {
+    pub fn kill_storage(&mut self, path: PlacePath, span: Span) {
+        traceln!(self.config, "storage of {} is dead", self.get_place_name(&path));
+        self.dead_storage.insert(path, span);
+    }
+
+    // A local whose storage dies loses its borrow stacks, pointers to it are no longer live
+    pub fn storage_dead(&mut self, local: u32, span: Span) {
+        self.record_may_alias();
+        let path = PlacePath::from_local(local);
+        self.stacked_borrows.deallocate(&path);
+        self.kill_storage(path, span);
+    }
+
+    // New storage is only accessible through the local, the stacks of a previous use of it are gone
+    pub fn storage_live(&mut self, local: u32) {
+        let path = PlacePath::from_local(local);
+        self.stacked_borrows.deallocate(&path);
+        self.stacked_borrows.allocate(local, Tag::Tagged(local), Permission::Unique);
+        self.revive_storage(&path);
+    }
+
+    // Assigning or allocating storage again makes it live, with all of its fields
+    pub fn revive_storage(&mut self, path: &PlacePath) {
+        self.dead_storage.retain(|dead, _| !path.is_prefix_of(dead));
+        self.moved_out.retain(|moved| !path.is_prefix_of(moved));
+    }
+
+    // A local moved to another place or into a call no longer holds its value
+    pub fn move_out(&mut self, place: &Place<'tcx>) {
+        if place.projection.is_empty() {
+            self.moved_out.insert(self.place_to_path(place));
+        }
+    }
+
+    // A borrower whose storage is dead or whose value was moved out no longer shares the memory
+    pub fn is_live_borrower(&self, borrower: &PlacePath) -> bool {
+        let is_moved = self.moved_out.iter().any(|moved| moved.is_prefix_of(borrower));
+        !is_moved && !self.dead_storage.keys().any(|dead| dead.is_prefix_of(borrower))
+    }
+
+    // Memory with two or more live borrowers, checked before a borrower stops being live. Only the analyzed
+    // functions report it, the callees are seen through their summaries
+    pub fn record_may_alias(&mut self) {
+        if self.call_site.is_some() {
+            return;
+        }
+        for place in self.alias_graph.aliasing_test(|borrower| self.is_live_borrower(borrower)) {
+            if self.may_alias.iter().any(|other| other.is_prefix_of(&place)) {
+                continue;
+            }
+            self.may_alias.retain(|other| !place.is_prefix_of(other));
+            self.may_alias.push(place);
+        }
+    }
+
+    // A dropped value is dead, dropping the owner of a Box or Vec also frees its heap object
+    pub fn drop_storage(&mut self, place: &Place<'tcx>, span: Span) {
+        if let Some(object) = self.drop_heap_object(place) {
+            self.kill_storage(object, span);
+        }
+        if !place.is_indirect() {
+            self.kill_storage(self.place_to_path(place), span);
+        }
+    }
+
+    // Dead storage that may be accessed through the first deref of the place
+    pub fn check_dangling(&mut self, place: &Place) {
+        let path = self.place_to_path(place);
+        let deref = match path.projection.iter().position(|elem| *elem == PathElem::Deref) {
+            Some(deref) => deref,
+            None => return,
+        };
+        let pointer = PlacePath { base: path.base, projection: path.projection[..deref].to_vec() };
+        for pointee in self.alias_graph.pointees(&pointer) {
+            let dead: Vec<PlacePath> = self.dead_storage.keys()
+                .filter(|dead| dead.overlaps(&pointee))
+                .cloned()
+                .collect();
+            for dead in dead {
+                if !self.dangling_accesses.contains(&(pointer.clone(), dead.clone())) {
+                    self.dangling_accesses.push((pointer.clone(), dead));
+                }
+            }
+        }
+    }
+
+    pub fn report_dangling_accesses(&mut self, location: Location, span: Span) {
+        for (pointer, dead) in std::mem::take(&mut self.dangling_accesses) {
+            let pointer_name = self.get_place_name(&pointer);
+            let dead_name = self.get_place_name(&dead);
+            let mut finding = self.new_finding(
+                FindingKind::DanglingPointer,
+                Severity::Error,
+                span,
+                format!("dereference of `{}` that may point to dead `{}`", pointer_name, dead_name),
+            );
+            finding.location = Some(location);
+            finding.locals = pointer.local().into_iter().chain(dead.local()).collect();
+            if let Some(died) = self.dead_storage.get(&dead) {
+                finding.span_note(*died, format!("`{}` died here", dead_name));
+            }
+            self.add_finding(finding);
+        }
+    }
+
+    // The returned value can not point to storage of the function that is already dead
+    pub fn check_returned_pointers(&mut self, location: Location, span: Span) {
+        let returned: Vec<PlacePath> = self.alias_graph.places()
+            .filter(|place| place.local() == Some(0) && !place.projection.contains(&PathElem::Deref))
+            .cloned()
+            .collect();
+        let mut reported: Vec<PlacePath> = Vec::new();
+        for place in returned {
+            for pointee in self.alias_graph.pointees(&place) {
+                let dead_storage: Vec<(PlacePath, Span)> = self.dead_storage.iter()
+                    .filter(|(dead, _)| dead.overlaps(&pointee) && !reported.contains(dead))
+                    .map(|(dead, died)| (dead.clone(), *died))
+                    .collect();
+                for (dead, died) in dead_storage {
+                    let dead_name = self.get_place_name(&dead);
+                    let mut finding = self.new_finding(
+                        FindingKind::DanglingPointer,
+                        Severity::Error,
+                        span,
+                        format!("returning a pointer to `{}` that is dead when the function returns", dead_name),
+                    );
+                    finding.location = Some(location);
+                    finding.locals = dead.local().into_iter().collect();
+                    finding.span_note(died, format!("`{}` died here", dead_name));
+                    self.add_finding(finding);
+                    reported.push(dead);
+                }
+            }
+        }
+    }
+}
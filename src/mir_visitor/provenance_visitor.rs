@@ -0,0 +1,93 @@
+use rustc_middle::mir::{Operand, Place};
+use rustc_middle::ty::{Ty, TyKind};
+
+use crate::places::PlacePath;
+use super::body_visitor::MirVisitor;
+
+// Pointers cast to integers and back, a pointer made from an integer may use any tag whose address was exposed
+impl<'tcx> MirVisitor<'tcx> {
+    // Casts between pointers and integers, false if the cast keeps the provenance of the operand
+    pub fn cast_provenance(&mut self, place: &Place<'tcx>, operand: &Operand<'tcx>, ty: Ty<'tcx>) -> bool {
+        let operand_ty = self.monomorphize(operand.ty(&self.local_declarations, self.tcx));
+        let ty = self.monomorphize(ty);
+        if operand_ty.is_unsafe_ptr() && ty.is_integral() {
+            self.exposed_address(place, operand);
+            true
+        } else if operand_ty.is_integral() && ty.is_unsafe_ptr() {
+            self.wildcard_pointer(place, Some(operand));
+            true
+        } else {
+            false
+        }
+    }
+
+    // Strict provenance functions and foreign functions, the analysis does not see what they do with pointers
+    pub fn call_provenance(&mut self, func: &Operand<'tcx>, args: &[Operand<'tcx>], destination: Option<Place<'tcx>>) {
+        let def_id = match func.constant().map(|constant| constant.literal.ty().kind()) {
+            Some(TyKind::FnDef(def_id, _)) => *def_id,
+            _ => return,
+        };
+        match (self.tcx.item_name(def_id).as_str(), destination) {
+            ("expose_addr", Some(place)) => {
+                if let Some(pointer) = args.first() {
+                    self.exposed_address(&place, pointer);
+                }
+            }
+            ("from_exposed_addr" | "from_exposed_addr_mut", Some(place)) => self.wildcard_pointer(&place, args.first()),
+            _ if self.tcx.is_foreign_item(def_id) => {
+                // Foreign code may keep the address of any pointer it gets
+                for arg in args {
+                    self.expose(arg);
+                }
+                if let Some(place) = destination {
+                    if self.is_raw_ptr(&place) {
+                        self.wildcard_pointer(&place, None);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The integer the pointer is cast to keeps the location it points into
+    fn exposed_address(&mut self, place: &Place, pointer: &Operand) {
+        if let Some(pointee) = self.expose(pointer) {
+            if !place.is_indirect() && place.projection.is_empty() {
+                self.stacked_borrows.addresses.insert(self.place_to_tag(place), pointee);
+            }
+        }
+    }
+
+    // The tag of the pointer is exposed in the location it points into
+    fn expose(&mut self, pointer: &Operand) -> Option<PlacePath> {
+        let pointer_place = match pointer {
+            Operand::Move(pointer_place) | Operand::Copy(pointer_place) if pointer_place.projection.is_empty() => pointer_place,
+            _ => return None,
+        };
+        let pointer_tag = self.place_to_tag(pointer_place);
+        let pointee = self.stacked_borrows.provenance.get(&pointer_tag).cloned()?;
+        traceln!(self.config, "expose {}", self.get_variable_name(pointer_place.local.as_u32()));
+        self.stacked_borrows.expose(&pointee, pointer_tag);
+        Some(pointee)
+    }
+
+    // A pointer made from an integer has wildcard provenance, it points into the location of the exposed
+    // address the integer holds, or into memory the analysis does not know
+    fn wildcard_pointer(&mut self, place: &Place, address: Option<&Operand>) {
+        if place.is_indirect() || !place.projection.is_empty() {
+            return;
+        }
+        let tag = self.place_to_tag(place);
+        traceln!(self.config, "wildcard {}", self.get_variable_name(place.local.as_u32()));
+        self.stacked_borrows.wildcards.insert(tag);
+        let location = match address {
+            Some(Operand::Move(address_place) | Operand::Copy(address_place)) if address_place.projection.is_empty() => {
+                self.stacked_borrows.addresses.get(&self.place_to_tag(address_place)).cloned()
+            }
+            _ => None,
+        };
+        if let Some(location) = location {
+            self.stacked_borrows.provenance.insert(tag, location);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use rustc_middle::mir::{Local, Location};
+use rustc_span::Span;
+
+use crate::findings::{Finding, FindingKind, Severity};
+use crate::stacked_borrows::{*};
+use super::body_visitor::MirVisitor;
+
+// Collection of the analysis findings
+impl<'tcx> MirVisitor<'tcx> {
+    // Collect the stacked borrows violations produced by the statement or terminator at location
+    pub fn report_stack_violations(&mut self, location: Location, span: Span) {
+        for creation in self.stacked_borrows.take_creations() {
+            self.record_borrow_event(creation.tag, location, span, BorrowEvent::Created(creation));
+        }
+        let invalidations: Vec<Invalidation> = self.stacked_borrows.take_invalidations();
+        for invalidation in invalidations {
+            self.record_borrow_event(invalidation.tag, location, span, BorrowEvent::Invalidated(invalidation));
+            if invalidation.protected {
+                self.report_protector_violation(invalidation, location, span);
+            }
+        }
+
+        let violations: Vec<Violation> = self.stacked_borrows.take_violations();
+        for violation in violations {
+            // The access that popped a protected item is already a protector violation
+            if self.was_protected(violation.tag) {
+                continue;
+            }
+            let name = self.stacked_borrows.get_tag_name(violation.tag);
+            let message = match violation.tag {
+                Tag::Untagged => format!("no exposed tag grants {} access to the wildcard pointer", violation.access),
+                Tag::Tagged(_) => format!("tag `{}` does not have {} access", name, violation.access),
+            };
+            let mut finding = self.new_finding(FindingKind::StackedBorrowsViolation, Severity::Error, span, message);
+            finding.location = Some(location);
+            finding.tags.push(violation.tag);
+            self.add_borrow_history(&mut finding, violation.tag);
+            self.replace_finding(finding);
+        }
+    }
+
+    fn was_protected(&self, tag: Tag) -> bool {
+        self.borrow_history.get(&tag).map_or(false, |history| {
+            history.iter().any(|(_, _, event)| matches!(event, BorrowEvent::Invalidated(Invalidation { protected: true, .. })))
+        })
+    }
+
+    // The arguments are borrowed before the first statement, where they are declared
+    pub fn record_argument_history(&mut self) {
+        for creation in self.stacked_borrows.take_creations() {
+            let span = match creation.tag {
+                Tag::Tagged(local) => self.body.local_decls.get(Local::from_u32(local)).map_or(self.body.span, |decl| decl.source_info.span),
+                Tag::Untagged => self.body.span,
+            };
+            self.record_borrow_event(creation.tag, Location::START, span, BorrowEvent::Created(creation));
+        }
+    }
+
+    fn record_borrow_event(&mut self, tag: Tag, location: Location, span: Span, event: BorrowEvent) {
+        let history = self.borrow_history.entry(tag).or_default();
+        if !history.contains(&(location, span, event)) {
+            traceln!(self.config, "{:?} {:?}", location, event);
+            history.push((location, span, event));
+        }
+    }
+
+    // Creations and invalidations of the tag, like the tracked pointer tags of Miri
+    fn add_borrow_history(&self, finding: &mut Finding, tag: Tag) {
+        let name = self.stacked_borrows.get_tag_name(tag);
+        let history = self.borrow_history.get(&tag).cloned().unwrap_or_default();
+        if !history.iter().any(|(_, _, event)| matches!(event, BorrowEvent::Created(_))) {
+            if let Some(created) = self.tag_spans.get(&tag) {
+                finding.span_note(*created, format!("`{}` was created here", name));
+            }
+        }
+        for (_, span, event) in history {
+            match event {
+                BorrowEvent::Created(Creation { derived_from: Some(parent), permission, .. }) => {
+                    let parent_name = self.stacked_borrows.get_tag_name(parent);
+                    finding.span_note(span, format!("`{}` was created here as {:?} from `{}`", name, permission, parent_name));
+                }
+                BorrowEvent::Created(Creation { permission, .. }) => {
+                    finding.span_note(span, format!("`{}` was created here as {:?}", name, permission));
+                }
+                BorrowEvent::Invalidated(invalidation) => {
+                    let other_name = self.stacked_borrows.get_tag_name(invalidation.invalidated_by);
+                    if !finding.tags.contains(&invalidation.invalidated_by) {
+                        finding.tags.push(invalidation.invalidated_by);
+                    }
+                    finding.span_note(span, format!("`{}` was invalidated here by an access through `{}`", name, other_name));
+                }
+            }
+        }
+    }
+
+    // An access popped the item of a reference argument before the function returned
+    fn report_protector_violation(&mut self, invalidation: Invalidation, location: Location, span: Span) {
+        let name = self.stacked_borrows.get_tag_name(invalidation.tag);
+        let other_name = self.stacked_borrows.get_tag_name(invalidation.invalidated_by);
+        let mut finding = self.new_finding(
+            FindingKind::ProtectorViolation,
+            Severity::Error,
+            span,
+            format!("access through `{}` invalidates the argument `{}` while the call is running", other_name, name),
+        );
+        finding.location = Some(location);
+        finding.tags = vec![invalidation.tag, invalidation.invalidated_by];
+        self.add_borrow_history(&mut finding, invalidation.tag);
+        match self.call_site {
+            Some(call_site) => finding.span_note(call_site, format!("`{}` is protected until this call returns", name)),
+            None => finding.note(format!("`{}` is protected until `{}` returns", name, self.tcx.def_path_str(self.body.source.def_id()))),
+        }
+        self.replace_finding(finding);
+    }
+
+    // MIR that is skipped, strict mode makes it a finding
+    pub fn report_unsupported(&mut self, location: Location, span: Span, message: String) {
+        traceln!(self.config, "{}", message);
+        if self.config.strict {
+            let mut finding = self.new_finding(FindingKind::Unsupported, Severity::Error, span, message);
+            finding.location = Some(location);
+            self.add_finding(finding);
+        }
+    }
+
+    pub fn new_finding(&self, kind: FindingKind, severity: Severity, span: Span, message: String) -> Finding {
+        Finding::new(kind, severity, self.body.source.def_id(), span, message)
+    }
+
+    // Blocks are visited more than once by the dataflow, keep a single copy of each finding
+    pub fn add_finding(&mut self, finding: Finding) {
+        if !self.findings.contains(&finding) {
+            self.findings.push(finding);
+        }
+    }
+
+    // A later visit of the statement knows more of the borrow history, its finding replaces the earlier one
+    fn replace_finding(&mut self, finding: Finding) {
+        let earlier = self.findings.iter().position(|other| {
+            other.kind == finding.kind && other.location == finding.location && other.span == finding.span && other.message == finding.message
+        });
+        match earlier {
+            Some(index) => self.findings[index] = finding,
+            None => self.findings.push(finding),
+        }
+    }
+}
@@ -3,15 +3,20 @@ use std::collections::HashMap;
 use rustc_middle::mir::{Location, Terminator};
 use rustc_middle::mir::Operand;
 use rustc_middle::mir::terminator::TerminatorKind;
-use rustc_middle::mir::ConstantKind;
-use rustc_middle::ty::TyKind;
+use rustc_middle::ty::Instance;
 
 
 use petgraph::dot::{Dot, Config};
 // use crate::utils::print_mir;
 use crate::stacked_borrows::{*};
+use crate::findings::{FindingKind, Severity};
+use crate::places::PlacePath;
+use crate::summary::FunctionSummary;
 use super::body_visitor::MirVisitor;
 
+// Upper bound of analyses of the head of a recursive cycle
+const MAX_FIXPOINT_ITERATIONS: usize = 10;
+
 // Visitor trait implementation
 impl<'tcx> MirVisitor<'tcx> {
     pub fn visit_terminator(
@@ -30,82 +35,117 @@ impl<'tcx> MirVisitor<'tcx> {
             } => {
                 //Get function return variable and argument names
                 if let Some((place, _)) = destination {
-                    println!("\twhere {:#?} is {}", place, self.get_variable_name(place.local.as_u32()));
+                    traceln!(self.config, "\twhere {:#?} is {}", place, self.get_variable_name(place.local.as_u32()));
                 }
                 for arg in &args {
                     let var = self.operand_as_u32(arg);
-                    print!("\tand _{:?} is {} ", var, self.get_variable_name(var));
+                    trace!(self.config, "\tand _{:?} is {} ", var, self.get_variable_name(var));
                 }
-
-                println!("\ncall {:#?}", &func);
-
-                // Visit arg
-                let mut index = 1;
-                let mut arg_refs: HashMap<u32, u32> = HashMap::new();
-                for arg in (&args).iter().rev() {
-                    // self.visit_operand(arg, location);
-                    arg_refs.insert(index, self.operand_as_u32(arg));
-                    index+=1;
+                self.record_may_alias();
+                for arg in &args {
+                    if let Operand::Move(place) = arg {
+                        self.move_out(place);
+                    }
                 }
 
+                traceln!(self.config, "\ncall {:#?}", &func);
+
                 // Check if there are 2 or more mutable arguments with alias
                 let mutable_args: Vec<Operand> = args.clone().drain_filter(|arg| self.is_mutable(arg)).collect();
                 if mutable_args.len() >= 2 {
-                    println!("Caution: This function call contains two or more mutable arguments");
+                    traceln!(self.config, "Caution: This function call contains two or more mutable arguments");
                     'args_loop: 
                     for i in 0..mutable_args.len() {
                         for j in i+1..mutable_args.len() {
-                            let (a, b) = (self.operand_as_u32(&mutable_args[i]), self.operand_as_u32(&mutable_args[j]));
-                            if self.alias_graph.are_alias(a,b) {
-                                println!("WARNING: Calling function with two mutable arguments that are alias");
+                            let (a, b) = match (self.operand_to_path(&mutable_args[i]), self.operand_to_path(&mutable_args[j])) {
+                                (Some(a), Some(b)) => (a, b),
+                                _ => continue,
+                            };
+                            if self.alias_graph.are_alias(&a, &b) {
+                                let mut finding = self.new_finding(
+                                    FindingKind::MutableArgumentsAlias,
+                                    Severity::Warning,
+                                    terminator.source_info.span,
+                                    String::from("calling function with two mutable arguments that are alias"),
+                                );
+                                finding.location = Some(location);
+                                finding.locals = a.local().into_iter().chain(b.local()).collect();
+                                finding.note(format!("`{}` and `{}` may point to the same memory", self.get_place_name(&a), self.get_place_name(&b)));
+                                self.add_finding(finding);
                                 break 'args_loop;
                             }
                         }                        
                     }                    
                 }
 
-                // Visit inside function
-                let constant = &func.constant().unwrap();
-                if let ConstantKind::Ty(cnst) = constant.literal {
-                    if cnst.ty.is_fn() {
-                        println!("const ty {:#?}", cnst.ty);
-                        if let TyKind::FnDef(def_id, subs_ref) = cnst.ty.kind() {
-                            // Ignore if it's a macro or if the mir is not available
-                            if !constant.span.from_expansion() && self.tcx.is_mir_available(*def_id) {
-                                let body = self.tcx.optimized_mir(*def_id);
-                                let mut visitor = MirVisitor::new(self.tcx, body, args);
-                                visitor.visit_body(body);
-
-                                println!(
-                                    "{:?}",
-                                    Dot::with_attr_getters(
-                                        &visitor.alias_graph.graph,
-                                        &[Config::EdgeNoLabel,Config::NodeNoLabel],
-                                        &|_, er| String::new(),
-                                        &|_, (index, refer)| format!("label = \"{}\"", visitor.get_variable_name(*refer)),
-                                    )
-                                );
-                                // self.alias_graph.extend(visitor.alias_graph.graph, arg_refs);
-                            }
-                        }
+                self.check_transmute(&func, &args, location, terminator.source_info.span);
+
+                // Constructors of heap objects are not visited, the result owns a new object
+                let allocation = self.allocation_kind(&func)
+                    .map(|kind| self.heap_object(kind, location, terminator.source_info.span));
+
+                // Visit every function that may be called
+                let mut summaries = Vec::new();
+                let targets = if allocation.is_some() { Vec::new() } else { self.call_targets(&func, &args) };
+                if allocation.is_none() && targets.is_empty() && self.is_dynamic_call(&func) {
+                    let message = String::from("the functions the dynamic call may execute are unknown, the call is not analyzed");
+                    self.report_unsupported(location, terminator.source_info.span, message);
+                }
+                for instance in targets {
+                    if !self.tcx.is_mir_available(instance.def_id()) {
+                        let message = format!("`{}` has no MIR, the call is not analyzed", instance);
+                        self.report_unsupported(location, terminator.source_info.span, message);
+                        continue;
                     }
+                    let callee_args = self.callee_arguments(instance, &func, &args);
+                    let summary = self.function_summary(instance, args.clone(), &callee_args, location);
+                    summaries.push((summary, callee_args));
                 }
 
                 // Add result variable to stack
                 if let Some((place, _)) = destination {
                     let tag = self.place_to_tag(&place);
+                    let destination_location = self.place_location(&place);
                     if !place.is_indirect() { // place does not contain a Deref
-                        self.stacked_borrows.new_ref(tag, Permission::Unique);
-                        self.alias_graph.constant(place.local.as_u32());
+                        self.tag_spans.insert(tag, terminator.source_info.span);
+                        self.stacked_borrows.forget_pointer(tag);
+                        self.stacked_borrows.new_ref(&destination_location, tag, Permission::Unique);
+                        self.alias_graph.constant(&self.place_to_path(&place));
+                    }
+                    self.forget_interval(&place);
+                    self.stacked_borrows.write(&destination_location, tag);
+                    if let Some(object) = allocation {
+                        self.allocate_heap_object(&place, object, &args);
+                    }
+                }
+                self.call_provenance(&func, &args, destination.map(|(place, _)| place));
+                self.vec_buffer_pointer(&args, destination.map(|(place, _)| place));
+                self.shared_owner_clone(&func, &args, destination.map(|(place, _)| place));
+
+                // Aliasing created by the callees
+                for (summary, mut arg_places) in summaries {
+                    if let Some((place, _)) = destination {
+                        arg_places.insert(0, self.place_to_path(&place));
+                    }
+                    self.alias_graph.extend(&summary.alias_graph, &arg_places);
+                    for freed in summary.freed {
+                        match freed.local() {
+                            Some(local) => if let Some(path) = arg_places.get(&local) {
+                                self.free_owned_objects(&path.clone(), terminator.source_info.span);
+                            },
+                            None => self.kill_storage(freed, terminator.source_info.span),
+                        }
                     }
-                    self.stacked_borrows.use_value(tag);
                 }
             },
             TerminatorKind::Assert {
                 cond,
+                expected,
+                msg,
                 ..
             } => {
                 self.visit_operand(&cond, location);
+                self.check_divisor(&cond, expected, &msg, location, terminator.source_info.span);
             },
             TerminatorKind::SwitchInt {
                 discr,
@@ -118,23 +158,125 @@ impl<'tcx> MirVisitor<'tcx> {
             TerminatorKind::Goto {
                 target
             } => {
-                // println!("goto {:#?}");
-                // self.visit_basic_block_data(target, self.body.index(target));
+                // Successors are visited by the worklist in visit_blocks
             },
             TerminatorKind::Drop {
                 place,
                 target,
                 unwind
             } => {
-                self.stacked_borrows.clean();
+                self.drop_storage(&place, terminator.source_info.span);
             }
-            TerminatorKind::Return
-            | TerminatorKind::Resume
+            TerminatorKind::Return => {
+                self.check_returned_pointers(location, terminator.source_info.span);
+            },
+            TerminatorKind::Resume
             | TerminatorKind::Unreachable
             => {},
-            _ => {
-                println!("Terminator Kind not recognized");
+            other => {
+                self.report_unsupported(location, terminator.source_info.span, format!("terminator `{:?}` is not supported", other));
+            }
+        }
+    }
+
+    // Analyze a callee the first time it is called in a context, later calls reuse its summary
+    fn function_summary(
+        &mut self,
+        instance: Instance<'tcx>,
+        args: Vec<Operand<'tcx>>,
+        callee_args: &HashMap<u32, PlacePath>,
+        location: Location
+    ) -> FunctionSummary {
+        let function_name = instance.to_string();
+        let mut call_string = self.call_string.clone();
+        call_string.push((self.body.source.def_id(), location));
+        let context = call_string[call_string.len().saturating_sub(self.config.context_depth)..].to_vec();
+        let key = (instance, context);
+
+        if let Some(summary) = self.summaries.summaries.get(&key) {
+            traceln!(self.config, "summary of {} already computed", function_name);
+            return summary.clone();
+        }
+
+        // Recursive call, use the summary computed so far
+        if let Some(position) = self.summaries.in_progress.iter().position(|in_progress| *in_progress == key) {
+            traceln!(self.config, "recursive call to {}", function_name);
+            let hit = self.summaries.recursive_hit.map_or(position, |hit| hit.min(position));
+            self.summaries.recursive_hit = Some(hit);
+            return self.summaries.approximation(&key);
+        }
+
+        // Without context a summary is shared by every call site, the aliasing of a single call can not be assumed
+        let aliased_args = if self.config.context_depth > 0 { self.aliased_args(callee_args) } else { Vec::new() };
+
+        let body = self.tcx.instance_mir(instance.def);
+        self.summaries.in_progress.push(key.clone());
+        let position = self.summaries.in_progress.len() - 1;
+        let mut iterations = 0;
+        let summary = loop {
+            iterations += 1;
+            let mut visitor = MirVisitor::new(self.tcx, body, args.clone(), self.config.clone());
+            visitor.instance = Some(instance);
+            visitor.call_string = call_string.clone();
+            visitor.call_site = Some(self.body.source_info(location).span);
+            visitor.aliased_args = aliased_args.clone();
+            for (local, path) in callee_args {
+                visitor.add_fn_targets(&PlacePath::from_local(*local), self.fn_pointer_targets(path));
+                visitor.arg_pointee_types.insert(PlacePath::from_local(*local), self.pointee_types(path));
+            }
+            visitor.summaries = std::mem::take(&mut self.summaries);
+            visitor.visit_body(body);
+
+            traceln!(self.config,
+                "{:?}",
+                Dot::with_attr_getters(
+                    &visitor.alias_graph.graph,
+                    &[Config::EdgeNoLabel,Config::NodeNoLabel],
+                    &|_, er| String::new(),
+                    &|_, (index, refer)| format!("label = \"{}\"", visitor.get_place_name(refer)),
+                )
+            );
+
+            let summary = FunctionSummary::new(&visitor.alias_graph, &visitor.dead_storage, body.arg_count);
+            traceln!(self.config, "summary of {}: {:?}", function_name, summary.alias_graph.edges());
+            self.summaries = std::mem::take(&mut visitor.summaries);
+            // Findings inside other crates can not be fixed by the user, the violations they lead to in the crate are kept
+            for finding in visitor.findings.into_iter().filter(|finding| finding.function.is_local()) {
+                self.add_finding(finding);
+            }
+
+            // Head of a recursive cycle, analyze it again until its summary is stable
+            if self.summaries.recursive_hit == Some(position) {
+                self.summaries.recursive_hit = None;
+                if self.summaries.approximation(&key) != summary && iterations < MAX_FIXPOINT_ITERATIONS {
+                    self.summaries.approximations.insert(key.clone(), summary);
+                    continue;
+                }
+            }
+            break summary;
+        };
+        self.summaries.in_progress.pop();
+        self.summaries.approximations.remove(&key);
+
+        // Functions inside a cycle depend on summaries that are not stable yet
+        if self.summaries.recursive_hit.is_none() {
+            self.summaries.summaries.insert(key, summary.clone());
+        }
+        summary
+    }
+
+    // Pairs of callee arguments that may point to the same memory at the call site
+    fn aliased_args(&self, callee_args: &HashMap<u32, PlacePath>) -> Vec<(u32, u32)> {
+        let mut locals: Vec<&u32> = callee_args.keys().collect();
+        locals.sort();
+        let mut aliased = Vec::new();
+        for i in 0..locals.len() {
+            for j in i+1..locals.len() {
+                if self.alias_graph.are_alias(&callee_args[locals[i]], &callee_args[locals[j]]) {
+                    aliased.push((*locals[i], *locals[j]));
+                }
             }
         }
+        aliased
     }
 }
@@ -0,0 +1,106 @@
+use std::fmt;
+
+// Projection of a place path, a simplified version of the MIR projection elements
+#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+pub enum PathElem {
+    Deref,
+    Field(u32),
+    Index, // Any element of an array or slice, the index is not known statically
+    ConstantIndex(u64),
+    Downcast(u32),
+}
+
+// Memory a place path starts from
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum PlaceBase {
+    Local(u32),
+    Heap(u32), // Object created at an allocation site, numbered by the analysis
+}
+
+// Abstract memory location: a local or heap object followed by field, index and deref projections
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct PlacePath {
+    pub base: PlaceBase,
+    pub projection: Vec<PathElem>,
+}
+
+impl PlacePath {
+    pub fn from_local(local: u32) -> PlacePath {
+        PlacePath { base: PlaceBase::Local(local), projection: Vec::new() }
+    }
+
+    pub fn heap(site: u32) -> PlacePath {
+        PlacePath { base: PlaceBase::Heap(site), projection: Vec::new() }
+    }
+
+    // Local the path starts from, None for heap objects
+    pub fn local(&self) -> Option<u32> {
+        match self.base {
+            PlaceBase::Local(local) => Some(local),
+            PlaceBase::Heap(_) => None,
+        }
+    }
+
+    pub fn is_heap(&self) -> bool {
+        matches!(self.base, PlaceBase::Heap(_))
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.projection.is_empty()
+    }
+
+    // Place holding the pointer of the last deref of the path, (*x).0 is accessed through x
+    pub fn pointer(&self) -> Option<PlacePath> {
+        let deref = self.projection.iter().rposition(|elem| *elem == PathElem::Deref)?;
+        Some(PlacePath { base: self.base, projection: self.projection[..deref].to_vec() })
+    }
+
+    pub fn is_prefix_of(&self, other: &PlacePath) -> bool {
+        self.base == other.base
+            && self.projection.len() <= other.projection.len()
+            && self.projection.iter().zip(&other.projection).all(|(a, b)| a == b)
+    }
+
+    // Two paths overlap if they may share memory: one contains the other or they can not be told apart
+    pub fn overlaps(&self, other: &PlacePath) -> bool {
+        if self.base != other.base {
+            return false;
+        }
+        let common = self.projection.len().min(other.projection.len());
+        let compatible = self.projection.iter().zip(&other.projection).all(|(a, b)| match (a, b) {
+            (PathElem::Field(a), PathElem::Field(b)) => a == b,
+            (PathElem::ConstantIndex(a), PathElem::ConstantIndex(b)) => a == b,
+            _ => true, // Derefs, unknown indexes and enum variants may share memory
+        });
+
+        // The pointee of a deref is stored somewhere else, x does not contain (*x)
+        let (shorter, longer) = if self.projection.len() <= other.projection.len() { (self, other) } else { (other, self) };
+        let leaves_memory = longer.projection[common..].contains(&PathElem::Deref);
+        compatible && !leaves_memory && shorter.projection.len() == common
+    }
+
+    // Human readable path using the given name for the local or heap object
+    pub fn to_string_with(&self, base_name: &str) -> String {
+        let mut name = String::from(base_name);
+        for elem in &self.projection {
+            name = match elem {
+                PathElem::Deref => format!("(*{})", name),
+                PathElem::Field(field) => format!("{}.{}", name, field),
+                PathElem::Index => format!("{}[_]", name),
+                PathElem::ConstantIndex(index) => format!("{}[{}]", name, index),
+                PathElem::Downcast(variant) => format!("({} as variant#{})", name, variant),
+            };
+        }
+        name
+    }
+}
+
+impl fmt::Debug for PlacePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let base_name = match self.base {
+            PlaceBase::Local(local) => format!("_{}", local),
+            PlaceBase::Heap(site) => format!("heap#{}", site),
+        };
+        write!(f, "{}", self.to_string_with(&base_name))
+    }
+}
@@ -1,10 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use petgraph::graph::{Graph, NodeIndex};
-use petgraph::visit::{Dfs, EdgeRef};
+use petgraph::visit::EdgeRef;
 
+use crate::places::{PathElem, PlacePath};
+
+#[derive(Default, Clone)]
 pub struct PointsToGraph {
-    pub graph: Graph::<u32,()>,
-    variables: HashMap<u32, NodeIndex>
+    pub graph: Graph::<PlacePath,()>,
+    variables: HashMap<PlacePath, NodeIndex>,
+    borrowed: HashSet<PlacePath>, // Places whose address is taken, other edges copy the value of their target
+    borrows: HashSet<(PlacePath, PlacePath)>, // Edges that take the address of their target
 }
 
 impl PointsToGraph {
@@ -12,107 +17,309 @@ impl PointsToGraph {
         PointsToGraph {
             graph: Graph::new(),
             variables: HashMap::new(),
+            borrowed: HashSet::new(),
+            borrows: HashSet::new(),
         }
     }
 
-    pub fn get_variable(&self, a: u32) -> NodeIndex<u32> {
-        self.variables.get(&a).unwrap().to_owned()
+    pub fn get_variable(&self, a: &PlacePath) -> NodeIndex<u32> {
+        self.variables.get(a).unwrap().to_owned()
+    }
+
+    pub fn does_variable_exits(&self, a: &PlacePath) -> bool {
+        self.variables.contains_key(a)
     }
 
-    pub fn does_variable_exits(&self, a: u32) -> bool {
-        match self.variables.get(&a) {
-            Some(variable) => true,
-            None => false,
+    fn get_or_add_variable(&mut self, a: &PlacePath) -> NodeIndex<u32> {
+        match self.variables.get(a) {
+            Some(node) => node.to_owned(),
+            None => {
+                let node = self.graph.add_node(a.clone());
+                self.variables.insert(a.clone(), node);
+                node
+            }
         }
     }
 
-    pub fn constant(&mut self, a: u32) {
-        if self.does_variable_exits(a) {
-            // Removes all outgoing edges
-            let graph_clone = self.graph.clone();
-            for edge in graph_clone.edges(self.get_variable(a)) {
-                self.graph.remove_edge(edge.id());
+    // Memory a place behind a deref may be, the same fields of whatever its pointer points to
+    fn deref_pointees(&self, a: &PlacePath) -> Vec<PlacePath> {
+        let pointer = match a.pointer() {
+            Some(pointer) => pointer,
+            None => return Vec::new(),
+        };
+        let fields = &a.projection[pointer.projection.len() + 1..];
+        self.variables.get(&pointer).into_iter()
+            .flat_map(|node| self.graph.neighbors(*node))
+            .map(|target| {
+                let mut pointee = self.graph[target].clone();
+                pointee.projection.extend(fields.iter().copied());
+                pointee
+            })
+            .collect()
+    }
+
+    // Nodes of a place and its fields, and the targets of the places that contain it since their value
+    // includes the value of the place
+    fn place_nodes(&self, a: &PlacePath) -> Vec<NodeIndex<u32>> {
+        let mut nodes = Vec::new();
+        for (path, node) in self.variables.iter().filter(|(path, _)| path.overlaps(a)) {
+            if path.projection.len() >= a.projection.len() {
+                nodes.push(*node);
+            } else {
+                nodes.extend(self.graph.neighbors(*node));
+            }
+        }
+        nodes
+    }
+
+    pub fn constant(&mut self, a: &PlacePath) {
+        self.get_or_add_variable(a);
+
+        // Removes all outgoing edges of the place and its fields
+        let graph_clone = self.graph.clone();
+        for (path, node) in &self.variables {
+            if a.is_prefix_of(path) {
+                for edge in graph_clone.edges(*node) {
+                    self.graph.remove_edge(self.graph.find_edge(edge.source(), edge.target()).unwrap());
+                }
             }
-        } else {
-            self.variables.insert(a, self.graph.add_node(a));
         }
 
-        // println!("{:?} | Added {}", self.variables, a);
+        // println!("{:?} | Added {:?}", self.variables, a);
     }
 
-    pub fn points_to(&mut self, a: u32, b: u32) {
-        let node;
-        if self.does_variable_exits(a) {
-            node = self.get_variable(a);
-        } else {
-            node = self.graph.add_node(a);
-            self.variables.insert(a, node);
+    pub fn points_to(&mut self, a: &PlacePath, b: &PlacePath) {
+        let node = self.get_or_add_variable(a);
+        let target = self.get_or_add_variable(b);
+
+        if self.graph.find_edge(node, target).is_none() {
+            self.graph.add_edge(node, target, ());
         }
 
-        self.graph.add_edge(node, self.get_variable(b), ());
-        // println!("{:?} | {} points to {}", self.variables, a, b);
+        // println!("{:?} | {:?} points to {:?}", self.variables, a, b);
     }
 
-    pub fn are_alias(&self, a:u32, b:u32) -> bool {
-        if !self.does_variable_exits(a) || !self.does_variable_exits(b) {
-            return false;
+    // a holds the address of b
+    pub fn borrow(&mut self, a: &PlacePath, b: &PlacePath) {
+        self.points_to(a, b);
+        self.borrowed.insert(b.clone());
+        self.borrows.insert((a.clone(), b.clone()));
+    }
+
+    pub fn is_borrowed(&self, a: &PlacePath) -> bool {
+        self.borrowed.contains(a)
+    }
+
+    pub fn edges(&self) -> HashSet<(PlacePath, PlacePath)> {
+        self.graph.raw_edges().iter()
+            .map(|edge| (self.graph[edge.source()].clone(), self.graph[edge.target()].clone()))
+            .collect()
+    }
+
+    // Merge the graph of another control flow path, a variable may point to anything it points to in either path
+    pub fn join(&mut self, other: &PointsToGraph) {
+        for variable in other.variables.keys() {
+            self.get_or_add_variable(variable);
         }
+        for (a, b) in other.edges() {
+            self.points_to(&a, &b);
+        }
+        self.borrowed.extend(other.borrowed.iter().cloned());
+        self.borrows.extend(other.borrows.iter().cloned());
+    }
 
-        let mut nodes_from_a = Vec::new();
-        let (a, b) = (self.get_variable(a), self.get_variable(b));
+    // Every place reachable from a, the fields of a reached place are reached too and a place behind
+    // a deref reaches the same fields of whatever its pointer points to
+    pub fn reachable_places(&self, a: &PlacePath) -> Vec<PlacePath> {
+        let mut reached: Vec<PlacePath> = Vec::new();
+        let mut stack = vec![a.clone()];
+        while let Some(place) = stack.pop() {
+            // The memory of a field is already part of the place that contains it
+            if reached.iter().any(|other| other.is_prefix_of(&place)) {
+                continue;
+            }
+            // Places that contain it hold its value, its fields are part of it
+            for (path, node) in self.variables.iter().filter(|(path, _)| path.overlaps(&place)) {
+                stack.extend(self.graph.neighbors(*node).map(|target| self.graph[target].clone()));
+                if path.projection.len() > place.projection.len() {
+                    reached.push(path.clone());
+                }
+            }
+            stack.extend(self.deref_pointees(&place));
+            reached.push(place);
+        }
+        reached
+    }
 
-        // Get all extended neighboor nodes starting from A
-        let mut dfs_a = Dfs::new(&self.graph, a);
-        while let Some(node) = dfs_a.next(&self.graph) {
-            nodes_from_a.push(node);
+    pub fn are_alias(&self, a: &PlacePath, b: &PlacePath) -> bool {
+        // Get all extended neighboor places starting from A
+        let places_from_a = self.reachable_places(a);
+
+        // Check if A and B reach places that share memory
+        self.reachable_places(b).iter().any(|place_b| {
+            places_from_a.iter().any(|place_a| place_a.overlaps(place_b))
+        })
+    }
+
+    fn is_borrow(&self, source: &PlacePath, target: &PlacePath) -> bool {
+        target.is_heap() || self.borrows.contains(&(source.clone(), target.clone()))
+    }
+
+    // Memory the value of a pointer points to, a place behind a pointer that is still live is kept as it is
+    // and a place behind a pointer that is no longer live is the same fields of whatever it pointed to
+    fn pointer_memory(&self, pointer: &PlacePath, is_live: &impl Fn(&PlacePath) -> bool, visited: &mut Vec<PlacePath>) -> Vec<PlacePath> {
+        let node = match self.variables.get(pointer) {
+            Some(node) if !visited.contains(pointer) => *node,
+            _ => return Vec::new(),
+        };
+        visited.push(pointer.clone());
+        let mut memory = Vec::new();
+        for target in self.graph.neighbors(node) {
+            let target = &self.graph[target];
+            if !self.is_borrow(pointer, target) {
+                // The value was copied from the target
+                memory.extend(self.pointer_memory(target, is_live, visited));
+                continue;
+            }
+            match target.pointer() {
+                Some(through) if !is_live(&through) => {
+                    let fields = &target.projection[through.projection.len() + 1..];
+                    memory.extend(self.pointer_memory(&through, is_live, visited).into_iter().map(|mut pointee| {
+                        pointee.projection.extend(fields.iter().copied());
+                        pointee
+                    }));
+                }
+                _ => memory.push(target.clone()),
+            }
+        }
+        memory
+    }
+
+    // Memory pointed to by two or more live borrowers, taking into account fields that overlap with it.
+    // A borrower is a live place that took an address, owns a heap object or got the pointer from a place
+    // that is no longer live, so a copy of a pointer that is still live is not another borrower
+    pub fn aliasing_test(&self, is_live: impl Fn(&PlacePath) -> bool) -> Vec<PlacePath> {
+        let mut borrows: Vec<(PlacePath, PlacePath)> = Vec::new();
+        for (place, node) in self.variables.iter() {
+            if place.projection.contains(&PathElem::Deref) || !is_live(place) {
+                continue;
+            }
+            let is_borrower = self.graph.neighbors(*node)
+                .any(|target| self.is_borrow(place, &self.graph[target]) || !is_live(&self.graph[target]));
+            if is_borrower {
+                for memory in self.pointer_memory(place, &is_live, &mut Vec::new()) {
+                    borrows.push((place.clone(), memory));
+                }
+            }
         }
 
-        // Check if A and B has a common extended neighboor node and return if that's the case
-        let mut dfs_b = Dfs::new(&self.graph, b);
-        while let Some(node) = dfs_b.next(&self.graph) {
-            if nodes_from_a.contains(&node) {
-                return true
+        let mut result: Vec<PlacePath> = Vec::new();
+        for (_, memory) in &borrows {
+            if result.contains(memory) {
+                continue;
+            }
+            let borrowers: HashSet<&PlacePath> = borrows.iter()
+                .filter(|(_, other)| other.overlaps(memory))
+                .map(|(borrower, _)| borrower)
+                .collect();
+            if borrowers.len() >= 2 {
+                result.push(memory.clone());
             }
         }
 
-        // Else return false
-        false
+        // Report only the outermost place, its fields are already included
+        let outermost = result.iter()
+            .filter(|place| !result.iter().any(|other| other != *place && other.is_prefix_of(place)))
+            .cloned()
+            .collect();
+        outermost
     }
 
-    pub fn aliasing_test(&self) -> Vec<usize> {
+    // Memory a pointer may point to, a pointee behind a deref is whatever its pointer points to
+    // and a place the value was copied from is followed to what it points to
+    pub fn pointees(&self, a: &PlacePath) -> Vec<PlacePath> {
         let mut result = Vec::new();
-        for index in self.graph.node_indices() {
-            let outgoing_edges = self.graph.neighbors(index).count();
-            let incoming_edges = self.graph.neighbors_undirected(index).count() - outgoing_edges;
-            if incoming_edges >= 2 {
-                let variable = self.graph.raw_nodes()[index.index()].weight as usize;
-                result.push(variable);
+        let mut visited = HashSet::new();
+        let mut stack = vec![a.clone()];
+        while let Some(pointer) = stack.pop() {
+            let node = match self.variables.get(&pointer) {
+                Some(node) if visited.insert(pointer.clone()) => *node,
+                _ => continue,
+            };
+            for target in self.graph.neighbors(node) {
+                let target = &self.graph[target];
+                match target.projection.iter().position(|elem| *elem == PathElem::Deref) {
+                    Some(deref) => stack.push(PlacePath { base: target.base, projection: target.projection[..deref].to_vec() }),
+                    None if !target.is_heap() && !self.borrowed.contains(target) => stack.push(target.clone()),
+                    None if !result.contains(target) => result.push(target.clone()),
+                    None => {}
+                }
             }
         }
         result
     }
 
-    pub fn extend(&mut self, graph: Graph::<u32,()>, args_ref: HashMap<u32, u32>) {
-        let mut variables: HashMap<u32, NodeIndex> = HashMap::new();
-        let cloned_graph = graph.clone();
-        let (nodes, edges) = cloned_graph.into_nodes_edges();
+    pub fn places(&self) -> impl Iterator<Item = &PlacePath> {
+        self.variables.keys()
+    }
 
-        for node in nodes {
-            let node_index = self.graph.add_node(node.weight);
-            if args_ref.contains_key(&node.weight) {
-                let real_arg = args_ref.get(&node.weight).unwrap().to_owned();
-                variables.insert(node.weight, self.get_variable(real_arg));
-            } else {
-                variables.insert(node.weight, node_index);
+    // Places reachable from a that satisfy is_target, without going past them
+    pub fn first_reachable(&self, a: &PlacePath, is_target: impl Fn(&PlacePath) -> bool) -> Vec<PlacePath> {
+        let mut result = Vec::new();
+        let start = match self.variables.get(a) {
+            Some(node) => *node,
+            None => return result,
+        };
+        let mut visited = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            let pointees = self.deref_pointees(&self.graph[node]);
+            for target in self.graph.neighbors(node).chain(pointees.iter().flat_map(|pointee| self.place_nodes(pointee))) {
+                if target == start || !visited.insert(target) {
+                    continue;
+                }
+                if is_target(&self.graph[target]) {
+                    result.push(self.graph[target].clone());
+                } else {
+                    stack.push(target);
+                }
             }
         }
+        result
+    }
 
-        for edge in edges {
-            let a = variables.get(graph.node_weight(edge.source()).unwrap()).unwrap().to_owned();
-            let b = variables.get(graph.node_weight(edge.target()).unwrap()).unwrap().to_owned();
-            self.graph.add_edge(a, b, ());
+    // Add the edges of a callee summary, renaming its return place and arguments with the caller places
+    pub fn extend(&mut self, summary: &PointsToGraph, renames: &HashMap<u32, PlacePath>) {
+        // Heap objects keep their allocation site in every function
+        let rename = |path: &PlacePath| match path.local() {
+            Some(local) => renames.get(&local).map(|place| {
+                let mut renamed = place.clone();
+                renamed.projection.extend(path.projection.iter());
+                renamed
+            }),
+            None => Some(path.clone()),
+        };
+
+        for (a, b) in summary.edges() {
+            if let (Some(renamed_a), Some(renamed_b)) = (rename(&a), rename(&b)) {
+                if renamed_a == renamed_b {
+                    continue;
+                }
+                if summary.borrowed.contains(&b) {
+                    self.borrow(&renamed_a, &renamed_b);
+                } else {
+                    self.points_to(&renamed_a, &renamed_b);
+                }
+            }
         }
     }
+}
 
-
-}
\ No newline at end of file
+impl PartialEq for PointsToGraph {
+    fn eq(&self, other: &Self) -> bool {
+        let variables: HashSet<&PlacePath> = self.variables.keys().collect();
+        let other_variables: HashSet<&PlacePath> = other.variables.keys().collect();
+        variables == other_variables && self.edges() == other.edges() && self.borrowed == other.borrowed
+            && self.borrows == other.borrows
+    }
+}
@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use serde_json::{json, Value};
+
+use crate::findings::Finding;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text, // Analysis trace and compiler diagnostics
+    Json, // Only the list of findings
+    Sarif, // SARIF 2.1.0 log for code scanning tools
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            other => Err(format!("Unknown output format `{}`, expected `text`, `json` or `sarif`", other)),
+        }
+    }
+}
+
+// Source file and 1-based line and column of the start and end of a span
+pub struct SourceRegion {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+pub fn source_region(tcx: TyCtxt, span: Span) -> SourceRegion {
+    let source_map = tcx.sess.source_map();
+    let start = source_map.lookup_char_pos(span.lo());
+    let end = source_map.lookup_char_pos(span.hi());
+    SourceRegion {
+        file: start.file.name.prefer_local().to_string(),
+        line: start.line,
+        column: start.col.0 + 1,
+        end_line: end.line,
+        end_column: end.col.0 + 1,
+    }
+}
+
+pub fn finding_to_json(tcx: TyCtxt, finding: &Finding) -> Value {
+    let region = source_region(tcx, finding.span);
+    let notes: Vec<Value> = finding.notes.iter().map(|note| {
+        let mut value = json!({ "message": note.message });
+        if let Some(span) = note.span {
+            let region = source_region(tcx, span);
+            value["file"] = json!(region.file);
+            value["line"] = json!(region.line);
+            value["column"] = json!(region.column);
+        }
+        value
+    }).collect();
+
+    json!({
+        "kind": finding.kind.to_string(),
+        "severity": finding.severity.to_string(),
+        "message": finding.message,
+        "file": region.file,
+        "line": region.line,
+        "column": region.column,
+        "end_line": region.end_line,
+        "end_column": region.end_column,
+        "function": tcx.def_path_str(finding.function),
+        "location": finding.location.map(|location| format!("{:?}", location)),
+        "tags": finding.tags.iter().map(|tag| format!("{:?}", tag)).collect::<Vec<String>>(),
+        "locals": finding.locals,
+        "notes": notes,
+    })
+}
+
+pub fn findings_to_json(tcx: TyCtxt, findings: &[Finding]) -> String {
+    let values: Vec<Value> = findings.iter().map(|finding| finding_to_json(tcx, finding)).collect();
+    serde_json::to_string_pretty(&values).unwrap()
+}
@@ -0,0 +1,251 @@
+use std::path::Path;
+
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use serde_json::{json, Value};
+
+use crate::findings::{Finding, FindingKind, Severity};
+use crate::report::source_region;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/eigiachero/rust-static-analyzer";
+
+// Base of the artifact locations inside the directory rsaa runs in
+const SRCROOT: &str = "SRCROOT";
+
+// Every finding kind reported as a SARIF rule
+const RULE_KINDS: [FindingKind; 12] = [
+    FindingKind::StackedBorrowsViolation,
+    FindingKind::MutableArgumentsAlias,
+    FindingKind::CastSize,
+    FindingKind::MayAlias,
+    FindingKind::DanglingPointer,
+    FindingKind::DivisionByZero,
+    FindingKind::AlignmentIncrease,
+    FindingKind::ConstToMutCast,
+    FindingKind::TransmuteSize,
+    FindingKind::InvalidTransmute,
+    FindingKind::Unsupported,
+    FindingKind::ProtectorViolation,
+];
+
+// Rule id, name, short description and help text of a finding kind
+fn rule(kind: FindingKind) -> (&'static str, &'static str, &'static str, &'static str) {
+    match kind {
+        FindingKind::StackedBorrowsViolation => (
+            "RSAA0001",
+            "StackedBorrowsViolation",
+            "Pointer used after being invalidated in the borrow stack",
+            "A reference or raw pointer was used after another access popped its item from the borrow stack \
+             of the memory location. Under the Stacked Borrows aliasing model this access is undefined behavior.",
+        ),
+        FindingKind::MutableArgumentsAlias => (
+            "RSAA0002",
+            "MutableArgumentsAlias",
+            "Function called with two mutable arguments that alias",
+            "Two mutable arguments of the call may point to the same memory. Mutable references must be unique, \
+             the callee is allowed to assume they do not overlap.",
+        ),
+        FindingKind::CastSize => (
+            "RSAA0003",
+            "CastSize",
+            "Cast to a type with a smaller layout",
+            "The value or pointee is cast to a type whose layout is smaller than the original one, \
+             the remaining bytes are lost or reinterpreted.",
+        ),
+        FindingKind::MayAlias => (
+            "RSAA0004",
+            "MayAlias",
+            "Variable pointed to by two or more variables",
+            "The variable may be accessed through more than one reference or pointer.",
+        ),
+        FindingKind::DanglingPointer => (
+            "RSAA0005",
+            "DanglingPointer",
+            "Pointer to dead or freed memory is used",
+            "A pointer is dereferenced or returned while the local or heap object it points to may already be \
+             dead or freed. Accessing it is a use after free and undefined behavior.",
+        ),
+        FindingKind::DivisionByZero => (
+            "RSAA0006",
+            "DivisionByZero",
+            "Division or remainder by a value that may be zero",
+            "The propagated values of the divisor include zero and no condition on the path rules it out. \
+             Dividing an integer by zero panics at runtime.",
+        ),
+        FindingKind::AlignmentIncrease => (
+            "RSAA0007",
+            "AlignmentIncrease",
+            "Pointer cast to a type with a stricter alignment",
+            "The pointer is cast to a pointee type that requires a larger alignment than the original one. \
+             Dereferencing it is undefined behavior unless the address happens to be aligned.",
+        ),
+        FindingKind::ConstToMutCast => (
+            "RSAA0008",
+            "ConstToMutCast",
+            "Const pointer cast to a mutable pointer with a mismatched layout",
+            "A read-only pointer is cast to a mutable pointer whose pointee is larger or more aligned. \
+             Writing through it may modify memory the original pointer did not cover or was not allowed to write.",
+        ),
+        FindingKind::TransmuteSize => (
+            "RSAA0009",
+            "TransmuteSize",
+            "Transmute reads more bytes than the source value has",
+            "transmute_copy reads a value of the target type from the source, if the target is larger \
+             the read goes past the end of the source value.",
+        ),
+        FindingKind::InvalidTransmute => (
+            "RSAA0010",
+            "InvalidTransmute",
+            "Transmute into a type that does not accept the value",
+            "The transmuted value may not be a valid bit pattern of the target type, such as a bool other than \
+             0 or 1, a char outside the Unicode scalar values, an enum without that discriminant or a null reference.",
+        ),
+        FindingKind::Unsupported => (
+            "RSAA0011",
+            "Unsupported",
+            "MIR construct not modeled by the analysis",
+            "The statement, terminator or value is skipped by the analysis, so its effects on the borrow stacks \
+             and the points-to graph are missing and other findings of the function may be wrong or incomplete.",
+        ),
+        FindingKind::ProtectorViolation => (
+            "RSAA0012",
+            "ProtectorViolation",
+            "Reference argument invalidated during the call",
+            "The item of a reference passed as argument is protected until the function returns. An access \
+             inside the function popped or disabled it through an aliasing pointer, which is undefined behavior \
+             under Stacked Borrows even if the reference is not used again.",
+        ),
+    }
+}
+
+fn rule_to_json(kind: FindingKind) -> Value {
+    let (id, name, short_description, help) = rule(kind);
+    json!({
+        "id": id,
+        "name": name,
+        "shortDescription": { "text": short_description },
+        "fullDescription": { "text": help },
+        "help": { "text": help },
+        "defaultConfiguration": { "level": level(default_severity(kind)) },
+    })
+}
+
+fn default_severity(kind: FindingKind) -> Severity {
+    match kind {
+        FindingKind::StackedBorrowsViolation | FindingKind::DanglingPointer | FindingKind::DivisionByZero
+        | FindingKind::TransmuteSize | FindingKind::InvalidTransmute | FindingKind::Unsupported
+        | FindingKind::ProtectorViolation => Severity::Error,
+        _ => Severity::Warning,
+    }
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+// Path with the characters that are not allowed in a URI percent encoded
+fn encode_path(path: &Path) -> String {
+    let mut uri = String::new();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+fn file_uri(path: &Path) -> String {
+    format!("file://{}", encode_path(path))
+}
+
+// Files inside the root are relative to SRCROOT, the others such as the sources of the standard library are file URIs
+fn artifact_location(file: &str, root: &Path) -> Value {
+    let path = root.join(file);
+    match path.strip_prefix(root) {
+        Ok(relative) => json!({ "uri": encode_path(relative), "uriBaseId": SRCROOT }),
+        Err(_) => json!({ "uri": file_uri(&path) }),
+    }
+}
+
+fn physical_location(tcx: TyCtxt, span: Span, root: &Path) -> Value {
+    let region = source_region(tcx, span);
+    json!({
+        "artifactLocation": artifact_location(&region.file, root),
+        "region": {
+            "startLine": region.line,
+            "startColumn": region.column,
+            "endLine": region.end_line,
+            "endColumn": region.end_column,
+        },
+    })
+}
+
+fn result_to_json(tcx: TyCtxt, finding: &Finding, root: &Path) -> Value {
+    let (rule_id, ..) = rule(finding.kind);
+    let rule_index = RULE_KINDS.iter().position(|kind| *kind == finding.kind).unwrap();
+
+    let mut related_locations = Vec::new();
+    let mut notes = Vec::new();
+    for note in &finding.notes {
+        match note.span {
+            Some(span) => related_locations.push(json!({
+                "id": related_locations.len(),
+                "physicalLocation": physical_location(tcx, span, root),
+                "message": { "text": note.message },
+            })),
+            None => notes.push(note.message.clone()),
+        }
+    }
+
+    let mut message = finding.message.clone();
+    for note in notes {
+        message = format!("{}\nnote: {}", message, note);
+    }
+
+    json!({
+        "ruleId": rule_id,
+        "ruleIndex": rule_index,
+        "level": level(finding.severity),
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": physical_location(tcx, finding.span, root),
+            "logicalLocations": [{
+                "fullyQualifiedName": tcx.def_path_str(finding.function),
+                "kind": "function",
+            }],
+        }],
+        "relatedLocations": related_locations,
+    })
+}
+
+// SARIF 2.1.0 log with a single run containing every finding
+pub fn findings_to_sarif(tcx: TyCtxt, findings: &[Finding]) -> String {
+    let rules: Vec<Value> = RULE_KINDS.iter().map(|kind| rule_to_json(*kind)).collect();
+    let root = std::env::current_dir().unwrap();
+    let results: Vec<Value> = findings.iter().map(|finding| result_to_json(tcx, finding, &root)).collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rsaa",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                SRCROOT: { "uri": format!("{}/", file_uri(&root)) },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
@@ -1,23 +1,27 @@
-use std::{fmt, collections::{VecDeque, HashMap}};
+use std::{fmt, collections::{HashMap, HashSet}};
 
+use crate::aliasing_model::{AliasingModel, AliasingModelKind, Borrows};
+use crate::places::PlacePath;
+
+// Items of the borrow stack of a location, from the bottom to the top
+#[derive(Default, Clone)]
 pub struct Stack {
-    borrows: VecDeque<StackItem>,
-    pub names: HashMap<u32, String>,
+    borrows: Vec<StackItem>,
+    pub violations: Vec<Violation>, // Accesses without a granting item, drained by the visitor
+    pub invalidations: Vec<Invalidation>, // Items popped or disabled by an access, drained by the visitor
+    pub creations: Vec<Creation>, // Items added by a reborrow or an allocation, drained by the visitor
+}
+
+// Only the borrows are part of the analysis state
+impl PartialEq for Stack {
+    fn eq(&self, other: &Self) -> bool {
+        self.borrows == other.borrows
+    }
 }
 
 impl fmt::Debug for Stack {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        for item in &self.borrows {
-            if item == *&self.borrows.back().unwrap() {
-                write!(f, "{:?}", item)?;
-            } else {
-                write!(f, "{:?}, ", item)?;
-            }
-
-        }
-        write!(f, "]")?;
-        Ok(())
+        f.debug_list().entries(self.borrows.iter()).finish()
     }
 }
 
@@ -26,11 +30,19 @@ impl fmt::Debug for Stack {
 pub struct StackItem {
     tag: Tag,
     permission: Permission,
+    protected: bool, // Item of a reference argument, it can not be popped while the call runs
+    exposed: bool, // The address of the tag was cast to an integer, wildcard pointers may use the item
 }
 
 impl fmt::Debug for StackItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}{:?}", self.permission, self.tag)?;
+        if self.protected {
+            write!(f, "(protected)")?;
+        }
+        if self.exposed {
+            write!(f, "(exposed)")?;
+        }
         Ok(())
     }
 }
@@ -40,113 +52,480 @@ pub enum Permission {
     Unique, // Grants unique mutable access.
     SharedReadWrite, // Grants shared mutable access.
     SharedReadOnly, // Grants shared read-only access.
+    Disabled, // Grants no access, a Unique item that was read through an item below it.
 }
+
+impl Permission {
+    pub fn grants(self, access: Access) -> bool {
+        !matches!((self, access), (Permission::Disabled, _) | (Permission::SharedReadOnly, Access::Write))
+    }
+}
+
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 pub enum Tag {
     Tagged(PtrId),
-    Untagged,
+    Untagged, // Wildcard provenance of a pointer cast from an integer, it may use any exposed tag
 }
 
 impl fmt::Debug for Tag {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Tag::Tagged(id) => write!(f, "<{}>", id),
-            Tag::Untagged => write!(f, "<untagged>"),
+            Tag::Untagged => write!(f, "<wildcard>"),
         }
     }
 }
 
 pub type PtrId = u32;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Read => write!(f, "READ"),
+            Access::Write => write!(f, "WRITE"),
+        }
+    }
+}
+
+// A tag was used but no item of the tag in the stack grants the access
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub tag: Tag,
+    pub access: Access,
+}
+
+// A tag was popped from the stack by an access through another tag
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Invalidation {
+    pub tag: Tag,
+    pub invalidated_by: Tag,
+    pub protected: bool, // The popped item was protected, the access itself is a violation
+}
+
+// A tag got a new item, derived_from is None for allocations and pointers whose parent is not known
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Creation {
+    pub tag: Tag,
+    pub derived_from: Option<Tag>,
+    pub permission: Permission,
+}
+
+// Something that happened to the borrows of a tag, in the order of the tag history
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BorrowEvent {
+    Created(Creation),
+    Invalidated(Invalidation),
+}
+
+// Access and reborrow rules of Stacked Borrows, as implemented by Miri
 impl Stack {
     pub fn new() -> Stack {
-        Stack { borrows: VecDeque::new(), names: HashMap::new() }
+        Stack::default()
     }
 
     pub fn clean(&mut self) {
         self.borrows.clear();
     }
 
-    pub fn new_ref(&mut self, tag: Tag, permission: Permission) {
-        let new_item = StackItem::new(tag, permission);
-        if self.borrows.contains(&new_item) {
-            self.use_value(tag);
-            return;
+    pub fn items(&self) -> &[StackItem] {
+        &self.borrows
+    }
+
+    // Merge the stack of another control flow path, only borrows valid in both paths are kept
+    pub fn join(&mut self, other: &Stack) {
+        self.borrows.retain(|item| other.borrows.contains(item));
+    }
+
+    // Topmost item of the tag that grants the access, a wildcard pointer uses the topmost exposed one
+    fn find_granting(&self, access: Access, tag: Tag) -> Option<usize> {
+        self.borrows.iter().rposition(|item| (item.tag == tag || tag == Tag::Untagged && item.exposed) && item.permission.grants(access))
+    }
+
+    // First item above the granting one that a write through it invalidates,
+    // the SharedReadWrite items right above a SharedReadWrite item share its access
+    fn find_first_write_incompatible(&self, granting: usize) -> usize {
+        let mut index = granting + 1;
+        if self.borrows[granting].permission == Permission::SharedReadWrite {
+            while index < self.borrows.len() && self.borrows[index].permission == Permission::SharedReadWrite {
+                index += 1;
+            }
+        }
+        index
+    }
+
+    fn violation(&mut self, tag: Tag, access: Access) {
+        self.violations.push(Violation { tag, access });
+    }
+
+    // A protected item that was already disabled has been reported when it was disabled
+    fn invalidate(&mut self, item: StackItem, invalidated_by: Tag) {
+        let protected = item.protected && item.permission != Permission::Disabled;
+        self.invalidations.push(Invalidation { tag: item.tag, invalidated_by, protected });
+    }
+
+    fn create(&mut self, tag: Tag, derived_from: Option<Tag>, permission: Permission) {
+        self.creations.push(Creation { tag, derived_from, permission });
+    }
+}
+
+impl AliasingModel for Stack {
+    // Item at the top of the stack for a new allocation or a pointer whose parent is not known
+    fn new_ref(&mut self, tag: Tag, permission: Permission) {
+        if !self.borrows.iter().any(|item| item.is(tag, permission)) {
+            self.borrows.push(StackItem::new(tag, permission));
+            self.create(tag, None, permission);
         }
-        self.borrows.push_front(new_item);
     }
 
-    pub fn use_value(&mut self, tag: Tag) {
-        loop  {
-            let last_item = self.borrows.pop_front();
-            match last_item {
-                Some(item) => {
-                    if item.tag == tag {
-                        self.borrows.push_front(item);
-                        break;
+    // Reborrow of derived_from, a new SharedReadWrite item is added next to its parent without
+    // an access, any other item is an access through the parent and goes to the top of the stack
+    fn grant(&mut self, derived_from: Tag, tag: Tag, permission: Permission) {
+        let access = if permission.grants(Access::Write) { Access::Write } else { Access::Read };
+        let granting = match self.find_granting(access, derived_from) {
+            Some(granting) => granting,
+            None => {
+                // Only the parent is reported, the new pointer is usable as if it were a new allocation
+                self.violation(derived_from, access);
+                self.new_ref(tag, permission);
+                return;
+            }
+        };
+        let index = if permission == Permission::SharedReadWrite {
+            self.find_first_write_incompatible(granting)
+        } else {
+            self.access(access, derived_from);
+            self.borrows.len()
+        };
+        let duplicate = (index > 0 && self.borrows[index - 1].is(tag, permission))
+            || self.borrows.get(index).map_or(false, |item| item.is(tag, permission));
+        if !duplicate {
+            self.borrows.insert(index, StackItem::new(tag, permission));
+            self.create(tag, Some(derived_from), permission);
+        }
+    }
+
+    // A copy of a raw pointer keeps the tag of the original, its items are shared with the copy
+    fn share(&mut self, from: Tag, to: Tag) {
+        let mut index = 0;
+        while index < self.borrows.len() {
+            let item = self.borrows[index];
+            if item.tag == from {
+                if !self.borrows.get(index + 1).map_or(false, |next| next.is(to, item.permission)) {
+                    self.borrows.insert(index + 1, StackItem::new(to, item.permission));
+                    self.create(to, Some(from), item.permission);
+                }
+                index += 1;
+            }
+            index += 1;
+        }
+    }
+
+    // A write pops every incompatible item above the granting one, a read disables the Unique items above it
+    fn access(&mut self, access: Access, tag: Tag) -> bool {
+        let granting = match self.find_granting(access, tag) {
+            Some(granting) => granting,
+            None => {
+                self.violation(tag, access);
+                return false;
+            }
+        };
+        match access {
+            Access::Write => {
+                let first_incompatible = self.find_first_write_incompatible(granting);
+                let popped: Vec<StackItem> = self.borrows.drain(first_incompatible..).rev().collect();
+                for item in popped {
+                    self.invalidate(item, tag);
+                }
+            }
+            Access::Read => {
+                let mut disabled = Vec::new();
+                for item in self.borrows[granting + 1..].iter_mut() {
+                    if item.permission == Permission::Unique {
+                        disabled.push(*item);
+                        item.permission = Permission::Disabled;
                     }
                 }
-                None => {
-                    println!("ERROR Tag {:?} does not have WRITE access", self.get_tag_name(tag));
-                    break;
+                for item in disabled {
+                    self.invalidate(item, tag);
                 }
-
             }
         }
+        true
     }
 
-    pub fn use_raw(&mut self, tag: Tag) {
-        if self.borrows.contains(&StackItem { tag, permission: Permission::SharedReadWrite }) {
-            loop  {
-                let last_item = self.borrows.pop_front();
-                match last_item {
-                    Some(item) => {
-                        if item.permission == Permission::SharedReadWrite {
-                            self.borrows.push_front(item);
-                            break;
-                        }
-                    }
-                    None => {
-                        println!("ERROR Tag {:?} does not have WRITE access", self.get_tag_name(tag));
-                        break;
-                    }
+    // The items of the tag are protected until the end of the analyzed call
+    fn protect(&mut self, tag: Tag) {
+        for item in self.borrows.iter_mut().filter(|item| item.tag == tag) {
+            item.protected = true;
+        }
+    }
 
-                }
+    fn expose(&mut self, tag: Tag) {
+        for item in self.borrows.iter_mut().filter(|item| item.tag == tag) {
+            item.exposed = true;
+        }
+    }
+
+    // A tag is live while one of its items grants some access
+    fn is_live(&self, tag: Tag) -> bool {
+        self.borrows.iter().any(|item| item.tag == tag && item.permission != Permission::Disabled)
+    }
+
+    // Like join, but the borrows of tags in keep survive if they are valid in either path
+    fn join_keeping(&mut self, other: &Stack, keep: &HashSet<Tag>) {
+        self.borrows.retain(|item| other.borrows.contains(item) || keep.contains(&item.tag));
+        for item in &other.borrows {
+            if keep.contains(&item.tag) && !self.borrows.contains(item) {
+                self.borrows.push(*item);
             }
+        }
+    }
+
+    fn take_violations(&mut self) -> Vec<Violation> {
+        std::mem::take(&mut self.violations)
+    }
+
+    fn take_invalidations(&mut self) -> Vec<Invalidation> {
+        std::mem::take(&mut self.invalidations)
+    }
+
+    fn take_creations(&mut self) -> Vec<Creation> {
+        std::mem::take(&mut self.creations)
+    }
+}
+
+impl StackItem {
+    pub fn new(tag: Tag, permission: Permission) -> StackItem {
+        StackItem { tag, permission, protected: false, exposed: false }
+    }
+
+    fn is(&self, tag: Tag, permission: Permission) -> bool {
+        self.tag == tag && self.permission == permission
+    }
+
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    pub fn permission(&self) -> Permission {
+        self.permission
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+
+    pub fn is_exposed(&self) -> bool {
+        self.exposed
+    }
+}
+
+// Borrows of every abstract memory location accessed by the function, a stack or a tree depending on the model
+#[derive(Default, Clone)]
+pub struct BorrowStacks {
+    model: AliasingModelKind,
+    stacks: HashMap<PlacePath, Borrows>,
+    pub provenance: HashMap<Tag, PlacePath>, // Location each reference or raw pointer points into
+    pub wildcards: HashSet<Tag>, // Raw pointers with wildcard provenance, cast from an integer or returned by FFI
+    pub addresses: HashMap<Tag, PlacePath>, // Location each integer holding an exposed address points into
+    pub names: HashMap<u32, String>,
+}
+
+impl PartialEq for BorrowStacks {
+    fn eq(&self, other: &Self) -> bool {
+        self.stacks == other.stacks && self.provenance == other.provenance
+            && self.wildcards == other.wildcards && self.addresses == other.addresses
+    }
+}
+
+impl fmt::Debug for BorrowStacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.stacks.iter()).finish()
+    }
+}
+
+impl BorrowStacks {
+    pub fn new(model: AliasingModelKind) -> BorrowStacks {
+        BorrowStacks { model, ..BorrowStacks::default() }
+    }
+
+    pub fn clean(&mut self) {
+        self.stacks.clear();
+    }
+
+    // A location without its own stack starts as a copy of the stack of the nearest place containing it
+    fn inherited_stack(&self, location: &PlacePath) -> Borrows {
+        let mut stack = self.stacks.iter()
+            .filter(|(path, _)| path.is_prefix_of(location))
+            .max_by_key(|(path, _)| path.projection.len())
+            .map(|(_, stack)| stack.clone())
+            .unwrap_or_else(|| self.model.new_borrows());
+        stack.take_violations();
+        stack.take_invalidations();
+        stack.take_creations();
+        stack
+    }
+
+    // Stacks of the location and every more precise location that shares memory with it
+    fn affected_locations(&mut self, location: &PlacePath) -> Vec<PlacePath> {
+        if !self.stacks.contains_key(location) {
+            let stack = self.inherited_stack(location);
+            self.stacks.insert(location.clone(), stack);
+        }
+        self.stacks.keys()
+            .filter(|path| path.projection.len() >= location.projection.len() && path.overlaps(location))
+            .cloned()
+            .collect()
+    }
+
+    fn apply(&mut self, location: &PlacePath, action: impl Fn(&mut Borrows)) {
+        for path in self.affected_locations(location) {
+            action(self.stacks.get_mut(&path).unwrap());
+        }
+    }
+
+    // Merge the stacks of another control flow path location by location
+    pub fn join(&mut self, other: &BorrowStacks) {
+        // A pointer assigned a different location in each path is not invalidated by the other path
+        let diverging: HashSet<Tag> = self.provenance.keys().chain(other.provenance.keys())
+            .filter(|tag| self.provenance.get(tag) != other.provenance.get(tag))
+            .cloned()
+            .collect();
+
+        let mut stacks = HashMap::new();
+        for location in self.stacks.keys().chain(other.stacks.keys()) {
+            let mut stack = self.inherited_stack(location);
+            stack.join_keeping(&other.inherited_stack(location), &diverging);
+            stacks.insert(location.clone(), stack);
+        }
+        self.stacks = stacks;
+
+        for (tag, location) in &other.provenance {
+            self.provenance.entry(*tag).or_insert_with(|| location.clone());
+        }
+        for (tag, location) in &other.addresses {
+            self.addresses.entry(*tag).or_insert_with(|| location.clone());
+        }
+        // A pointer that is a wildcard in one of the paths is checked as one
+        self.wildcards.extend(other.wildcards.iter().copied());
+    }
+
+    // The local is assigned a new value, it no longer holds the pointer or address it held
+    pub fn forget_pointer(&mut self, tag: Tag) {
+        self.provenance.remove(&tag);
+        self.wildcards.remove(&tag);
+        self.addresses.remove(&tag);
+    }
+
+    // Tag the model checks an access through the pointer with, a wildcard pointer into memory the analysis
+    // does not know, like memory allocated by FFI, is not checked
+    fn model_tag(&self, location: &PlacePath, tag: Tag) -> Option<Tag> {
+        if !self.wildcards.contains(&tag) || owns(location, tag) {
+            Some(tag)
+        } else if self.provenance.contains_key(&tag) {
+            Some(Tag::Untagged)
         } else {
-            println!("ERROR Tag {:?} does not have WRITE access", self.get_tag_name(tag));
+            None
         }
     }
 
-    pub fn read_raw(&mut self, tag: Tag) {
-        if !self.borrows.contains(&StackItem { tag, permission: Permission::SharedReadWrite }) {
-            println!("ERROR Tag {:?} does not have WRITE access", self.get_tag_name(tag));
+    // The first write to a local grants its own tag access to all of its fields
+    pub fn allocate(&mut self, local: u32, tag: Tag, permission: Permission) {
+        let model = self.model;
+        self.stacks.entry(PlacePath::from_local(local)).or_insert_with(|| {
+            let mut stack = model.new_borrows();
+            stack.new_ref(tag, permission);
+            stack
+        });
+    }
+
+    // Freed memory has no stack, every later access is reported
+    pub fn deallocate(&mut self, location: &PlacePath) {
+        self.stacks.retain(|path, _| !location.is_prefix_of(path));
+    }
+
+    pub fn new_ref(&mut self, location: &PlacePath, tag: Tag, permission: Permission) {
+        self.apply(location, |stack| stack.new_ref(tag, permission));
+    }
+
+    pub fn read(&mut self, location: &PlacePath, tag: Tag) {
+        if let Some(tag) = self.model_tag(location, tag) {
+            self.apply(location, |stack| stack.read(tag));
         }
     }
 
-    pub fn read_value(&mut self, tag: Tag) {
-        let mut index = 0;
-        for item in self.borrows.clone().into_iter(){
-            if item.tag == tag {
-                return;
+    pub fn write(&mut self, location: &PlacePath, tag: Tag) {
+        if let Some(tag) = self.model_tag(location, tag) {
+            self.apply(location, |stack| stack.write(tag));
+        }
+    }
+
+    pub fn grant(&mut self, location: &PlacePath, derived_from: Tag, tag: Tag, permission: Permission) {
+        match self.model_tag(location, derived_from) {
+            Some(derived_from) => self.apply(location, |stack| stack.grant(derived_from, tag, permission)),
+            None => self.new_ref(location, tag, permission),
+        }
+    }
+
+    pub fn share(&mut self, location: &PlacePath, from: Tag, to: Tag) {
+        self.apply(location, |stack| stack.share(from, to));
+    }
+
+    pub fn protect(&mut self, location: &PlacePath, tag: Tag) {
+        self.apply(location, |stack| stack.protect(tag));
+    }
+
+    pub fn expose(&mut self, location: &PlacePath, tag: Tag) {
+        self.apply(location, |stack| stack.expose(tag));
+    }
+
+    // An access to a location may violate the stacks of several of its fields, report it only once
+    pub fn take_violations(&mut self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for stack in self.stacks.values_mut() {
+            for violation in stack.take_violations() {
+                if !violations.contains(&violation) {
+                    violations.push(violation);
+                }
             }
-            if item.permission == Permission::Unique {
-                self.borrows.remove(index);
+        }
+        violations
+    }
+
+    pub fn take_invalidations(&mut self) -> Vec<Invalidation> {
+        let mut invalidations = Vec::new();
+        for stack in self.stacks.values_mut() {
+            for invalidation in stack.take_invalidations() {
+                if !invalidations.contains(&invalidation) {
+                    invalidations.push(invalidation);
+                }
             }
-            index += 0;
         }
-        println!("ERROR Tag {:?} does not have READ access", self.get_tag_name(tag));
+        invalidations
     }
 
-    pub fn is_live(&self, tag: Tag) -> bool {
-        let mut result = false;
-        for item in &self.borrows {
-            if item.tag == tag {
-                result = true;
-                break;
+    // The item a local gets in its own memory is not a borrow, it is left out of the tag history
+    pub fn take_creations(&mut self) -> Vec<Creation> {
+        let mut creations = Vec::new();
+        for (path, stack) in self.stacks.iter_mut() {
+            for creation in stack.take_creations() {
+                let owned = creation.derived_from.is_none() && owns(path, creation.tag);
+                if !owned && !creations.contains(&creation) {
+                    creations.push(creation);
+                }
             }
         }
-        result
+        creations
+    }
+
+    pub fn is_live(&self, tag: Tag) -> bool {
+        self.stacks.values().any(|stack| stack.is_live(tag))
     }
 
     pub fn get_tag_name(&self, tag: Tag) -> String {
@@ -160,8 +539,7 @@ impl Stack {
     }
 }
 
-impl StackItem {
-    pub fn new(tag: Tag, permission: Permission) -> StackItem {
-        StackItem { tag, permission }
-    }
+// The location is the storage of the local the tag belongs to, or a field of it
+fn owns(location: &PlacePath, tag: Tag) -> bool {
+    location.pointer().is_none() && location.local().map(Tag::Tagged) == Some(tag)
 }
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::Location;
+use rustc_middle::ty::Instance;
+use rustc_span::Span;
+
+use crate::heap::AllocationSite;
+use crate::places::{PathElem, PlacePath};
+use crate::points_to::PointsToGraph;
+
+// Call sites that lead to a function, from the outermost caller
+pub type CallString = Vec<(DefId, Location)>;
+
+// A function instance analyzed in the context of its last call sites
+pub type SummaryKey<'tcx> = (Instance<'tcx>, CallString);
+
+// Effect of a function on the points-to graph of its callers, in terms of the return place (_0) and the arguments
+#[derive(Default, Clone, PartialEq)]
+pub struct FunctionSummary {
+    pub alias_graph: PointsToGraph,
+    pub freed: Vec<PlacePath>, // Heap objects and arguments that may be freed when the function returns
+}
+
+impl FunctionSummary {
+    pub fn new(alias_graph: &PointsToGraph, dead_storage: &HashMap<PlacePath, Span>, arg_count: usize) -> FunctionSummary {
+        // Heap objects outlive the function, they are seen by the caller like the arguments
+        let is_interface = |path: &PlacePath| match path.local() {
+            Some(local) => local as usize <= arg_count,
+            None => true,
+        };
+
+        // Arguments are copies, only the return place and the memory behind the arguments are seen by the caller
+        let mut summary_graph = PointsToGraph::new();
+        for place in alias_graph.places() {
+            let is_visible = match place.local() {
+                Some(local) => local as usize <= arg_count && (local == 0 || place.projection.contains(&PathElem::Deref)),
+                None => true,
+            };
+            if !is_visible {
+                continue;
+            }
+            for target in alias_graph.first_reachable(place, is_interface) {
+                if alias_graph.is_borrowed(&target) {
+                    summary_graph.borrow(place, &target);
+                } else {
+                    summary_graph.points_to(place, &target);
+                }
+            }
+        }
+        // An argument dropped by the function frees what it owns in the caller
+        let mut freed: Vec<PlacePath> = dead_storage.keys()
+            .filter(|path| match path.local() {
+                Some(local) => path.is_local() && local != 0 && local as usize <= arg_count,
+                None => true,
+            })
+            .cloned()
+            .collect();
+        freed.sort_by_key(|path| format!("{:?}", path));
+        FunctionSummary { alias_graph: summary_graph, freed }
+    }
+}
+
+// Summaries and allocation sites shared by every visitor of an analysis
+#[derive(Default)]
+pub struct SummaryCache<'tcx> {
+    pub allocation_sites: Vec<AllocationSite>, // Indexed by the number of the heap object
+    pub summaries: HashMap<SummaryKey<'tcx>, FunctionSummary>,
+    pub approximations: HashMap<SummaryKey<'tcx>, FunctionSummary>, // Summaries of recursive functions that are not stable yet
+    pub in_progress: Vec<SummaryKey<'tcx>>, // Functions being analyzed, from the root to the innermost callee
+    pub recursive_hit: Option<usize>, // Outermost function in progress that was called again
+}
+
+impl<'tcx> SummaryCache<'tcx> {
+    pub fn approximation(&self, key: &SummaryKey<'tcx>) -> FunctionSummary {
+        self.approximations.get(key).cloned().unwrap_or_default()
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::aliasing_model::AliasingModel;
+use crate::stacked_borrows::{Access, Creation, Invalidation, Permission, Tag, Violation};
+
+// Permission of a node of the tree, ordered from the most to the least permissive
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TreePermission {
+    Reserved, // Mutable reference that was not written yet, reads through other pointers keep it writable
+    Active, // Mutable reference that was written through
+    Frozen, // Shared reference, or an active one after a read through a pointer that is not its parent
+    Disabled, // Grants no access
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    parent: Option<Tag>,
+    permission: TreePermission,
+    protected: bool, // Node of a reference argument, it can not be disabled while the call runs
+    exposed: bool, // The address of the tag was cast to an integer, wildcard pointers may access through the node
+}
+
+// Tags of a location as a tree, each reborrow is a child of the pointer it was derived from
+#[derive(Default, Clone)]
+pub struct Tree {
+    nodes: HashMap<Tag, Node>,
+    aliases: HashMap<Tag, Tag>, // Raw pointers and shared references to an UnsafeCell use the node of their parent
+    pub violations: Vec<Violation>,
+    pub invalidations: Vec<Invalidation>,
+    pub creations: Vec<Creation>,
+}
+
+// Only the nodes are part of the analysis state
+impl PartialEq for Tree {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes && self.aliases == other.aliases
+    }
+}
+
+impl fmt::Debug for Tree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut nodes: Vec<String> = self.nodes.iter().map(|(tag, node)| match node.parent {
+            Some(parent) => format!("{:?}{:?} <- {:?}", node.permission, tag, parent),
+            None => format!("{:?}{:?}", node.permission, tag),
+        }).collect();
+        nodes.sort();
+        f.debug_list().entries(nodes.iter()).finish()
+    }
+}
+
+// Access and reborrow rules of Tree Borrows
+impl Tree {
+    pub fn new() -> Tree {
+        Tree::default()
+    }
+
+    pub fn permission(&self, tag: Tag) -> Option<TreePermission> {
+        self.nodes.get(&self.node_of(tag)).map(|node| node.permission)
+    }
+
+    pub fn parent(&self, tag: Tag) -> Option<Tag> {
+        self.nodes.get(&self.node_of(tag)).and_then(|node| node.parent)
+    }
+
+    // Node the tag accesses the location through
+    fn node_of(&self, tag: Tag) -> Tag {
+        self.aliases.get(&tag).copied().unwrap_or(tag)
+    }
+
+    // The node and every parent of it up to the root
+    fn path_to_root(&self, node: Tag) -> Vec<Tag> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some(parent) = self.nodes.get(&current).and_then(|node| node.parent) {
+            if path.contains(&parent) {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+        path
+    }
+
+    fn initial_permission(permission: Permission) -> TreePermission {
+        match permission {
+            Permission::Unique => TreePermission::Reserved,
+            Permission::SharedReadWrite => TreePermission::Active,
+            Permission::SharedReadOnly => TreePermission::Frozen,
+            Permission::Disabled => TreePermission::Disabled,
+        }
+    }
+
+    // Permission after an access through the node or one of its children, None if the node does not allow it
+    fn child_access(permission: TreePermission, access: Access) -> Option<TreePermission> {
+        match (permission, access) {
+            (TreePermission::Disabled, _) | (TreePermission::Frozen, Access::Write) => None,
+            (_, Access::Write) => Some(TreePermission::Active),
+            (permission, Access::Read) => Some(permission),
+        }
+    }
+
+    // Permission after an access through a pointer that is not a child of the node
+    fn foreign_permission(node: &Node, access: Access) -> TreePermission {
+        match (node.permission, access) {
+            (_, Access::Write) => TreePermission::Disabled,
+            // A protected mutable reference can not lose its write access
+            (TreePermission::Active, Access::Read) if node.protected => TreePermission::Disabled,
+            (TreePermission::Active, Access::Read) => TreePermission::Frozen,
+            (permission, Access::Read) => permission,
+        }
+    }
+
+    // The access is allowed through the node and all of its parents
+    fn grants(&self, node: Tag, access: Access) -> bool {
+        self.path_to_root(node).iter()
+            .filter_map(|ancestor| self.nodes.get(ancestor))
+            .all(|node| Tree::child_access(node.permission, access).is_some())
+    }
+
+    // A wildcard pointer may access through any exposed node that grants the access, only the nodes
+    // that are foreign to all of them lose their permission
+    fn wildcard_access(&mut self, access: Access) -> bool {
+        let granting: Vec<Tag> = self.nodes.iter()
+            .filter(|(tag, node)| node.exposed && self.grants(**tag, access))
+            .map(|(tag, _)| *tag)
+            .collect();
+        if granting.is_empty() {
+            self.violation(Tag::Untagged, access);
+            return false;
+        }
+        let local: HashSet<Tag> = granting.iter().flat_map(|node| self.path_to_root(*node)).collect();
+        let foreign: Vec<Tag> = self.nodes.keys().filter(|other| !local.contains(other)).copied().collect();
+        self.foreign_access(&foreign, access, Tag::Untagged);
+        true
+    }
+
+    fn foreign_access(&mut self, foreign: &[Tag], access: Access, tag: Tag) {
+        for other in foreign {
+            let node = self.nodes[other];
+            let permission = Tree::foreign_permission(&node, access);
+            if permission != node.permission {
+                self.nodes.get_mut(other).unwrap().permission = permission;
+                let protected = node.protected && permission == TreePermission::Disabled;
+                self.invalidate(*other, tag, protected);
+            }
+        }
+    }
+
+    fn violation(&mut self, tag: Tag, access: Access) {
+        self.violations.push(Violation { tag, access });
+    }
+
+    // The node and the pointers that use it lost some access
+    fn invalidate(&mut self, node: Tag, invalidated_by: Tag, protected: bool) {
+        self.invalidations.push(Invalidation { tag: node, invalidated_by, protected });
+        let aliases: Vec<Tag> = self.aliases.iter().filter(|(_, aliased)| **aliased == node).map(|(tag, _)| *tag).collect();
+        for tag in aliases {
+            self.invalidations.push(Invalidation { tag, invalidated_by, protected: false });
+        }
+    }
+
+    fn create(&mut self, tag: Tag, derived_from: Option<Tag>, permission: Permission) {
+        self.creations.push(Creation { tag, derived_from, permission });
+    }
+}
+
+impl AliasingModel for Tree {
+    // Root of the tree for a new allocation or a pointer whose parent is not known
+    fn new_ref(&mut self, tag: Tag, permission: Permission) {
+        let node = self.node_of(tag);
+        if !self.nodes.contains_key(&node) {
+            self.nodes.insert(tag, Node { parent: None, permission: Tree::initial_permission(permission), protected: false, exposed: false });
+            self.create(tag, None, permission);
+        }
+    }
+
+    // Reborrows read through their parent and become a new child of it, raw pointers and shared references
+    // to an UnsafeCell do not get a node of their own
+    fn grant(&mut self, derived_from: Tag, tag: Tag, permission: Permission) {
+        // The parent of a reborrow of a wildcard pointer is not known, it becomes a new root
+        if derived_from == Tag::Untagged {
+            if self.access(Access::Read, derived_from) {
+                self.new_ref(tag, permission);
+            }
+            return;
+        }
+        let parent = self.node_of(derived_from);
+        if !self.nodes.contains_key(&parent) {
+            // Only the parent is reported, the new pointer is usable as if it were a new allocation
+            let access = if permission.grants(Access::Write) { Access::Write } else { Access::Read };
+            self.violation(derived_from, access);
+            self.new_ref(tag, permission);
+            return;
+        }
+        if permission == Permission::SharedReadWrite {
+            if tag != parent && self.aliases.insert(tag, parent) != Some(parent) {
+                self.nodes.remove(&tag);
+                self.create(tag, Some(derived_from), permission);
+            }
+            return;
+        }
+        if !self.access(Access::Read, derived_from) {
+            self.new_ref(tag, permission);
+            return;
+        }
+        // A reborrow of the pointer itself, or of one of its children, keeps its node
+        if self.path_to_root(parent).contains(&tag) {
+            return;
+        }
+        self.aliases.remove(&tag);
+        let node = Node { parent: Some(parent), permission: Tree::initial_permission(permission), protected: false, exposed: false };
+        if self.nodes.insert(tag, node) != Some(node) {
+            self.create(tag, Some(derived_from), permission);
+        }
+    }
+
+    fn share(&mut self, from: Tag, to: Tag) {
+        let node = self.node_of(from);
+        if self.nodes.contains_key(&node) && node != to && self.aliases.insert(to, node) != Some(node) {
+            self.nodes.remove(&to);
+            self.create(to, Some(from), Permission::SharedReadWrite);
+        }
+    }
+
+    // The access is a child access for the node and its parents, and a foreign access for every other node
+    fn access(&mut self, access: Access, tag: Tag) -> bool {
+        if tag == Tag::Untagged {
+            return self.wildcard_access(access);
+        }
+        let node = self.node_of(tag);
+        if !self.nodes.contains_key(&node) {
+            self.violation(tag, access);
+            return false;
+        }
+        let path = self.path_to_root(node);
+        let mut updates = Vec::new();
+        for ancestor in &path {
+            match self.nodes.get(ancestor).and_then(|node| Tree::child_access(node.permission, access)) {
+                Some(permission) => updates.push((*ancestor, permission)),
+                None if self.nodes.contains_key(ancestor) => {
+                    self.violation(tag, access);
+                    return false;
+                }
+                None => {}
+            }
+        }
+        for (ancestor, permission) in updates {
+            self.nodes.get_mut(&ancestor).unwrap().permission = permission;
+        }
+
+        let foreign: Vec<Tag> = self.nodes.keys().filter(|other| !path.contains(other)).copied().collect();
+        self.foreign_access(&foreign, access, tag);
+        true
+    }
+
+    fn protect(&mut self, tag: Tag) {
+        let node = self.node_of(tag);
+        if let Some(node) = self.nodes.get_mut(&node) {
+            node.protected = true;
+        }
+    }
+
+    fn expose(&mut self, tag: Tag) {
+        let node = self.node_of(tag);
+        if let Some(node) = self.nodes.get_mut(&node) {
+            node.exposed = true;
+        }
+    }
+
+    fn is_live(&self, tag: Tag) -> bool {
+        self.permission(tag).map_or(false, |permission| permission != TreePermission::Disabled)
+    }
+
+    // A node in both paths has the least permission of the two
+    fn join_keeping(&mut self, other: &Tree, keep: &HashSet<Tag>) {
+        let mut nodes = HashMap::new();
+        for (tag, node) in &self.nodes {
+            match other.nodes.get(tag) {
+                Some(other_node) => {
+                    let permission = node.permission.max(other_node.permission);
+                    let protected = node.protected || other_node.protected;
+                    let exposed = node.exposed || other_node.exposed;
+                    nodes.insert(*tag, Node { parent: node.parent, permission, protected, exposed });
+                }
+                None if keep.contains(tag) => {
+                    nodes.insert(*tag, *node);
+                }
+                None => {}
+            }
+        }
+        for (tag, node) in &other.nodes {
+            if keep.contains(tag) && !nodes.contains_key(tag) {
+                nodes.insert(*tag, *node);
+            }
+        }
+        self.nodes = nodes;
+        self.aliases.retain(|tag, node| other.aliases.get(tag) == Some(node) || keep.contains(tag));
+        for (tag, node) in &other.aliases {
+            if keep.contains(tag) {
+                self.aliases.entry(*tag).or_insert(*node);
+            }
+        }
+    }
+
+    fn take_violations(&mut self) -> Vec<Violation> {
+        std::mem::take(&mut self.violations)
+    }
+
+    fn take_invalidations(&mut self) -> Vec<Invalidation> {
+        std::mem::take(&mut self.invalidations)
+    }
+
+    fn take_creations(&mut self) -> Vec<Creation> {
+        std::mem::take(&mut self.creations)
+    }
+}
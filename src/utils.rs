@@ -5,13 +5,13 @@ use rustc_hir::def_id::DefId;
 
 // println!("{:#?}", _);
 
-pub fn print_mir<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) {
+pub fn print_mir(tcx: TyCtxt, def_id: DefId) {
     println!("Printing MIR for {:?}", def_id);
 
     if tcx.is_mir_available(def_id) {
         let stdout = std::io::stdout();
         let mut handle = stdout.lock();
-        if let Err(_) = write_mir_pretty(tcx, Some(def_id), &mut handle) {
+        if write_mir_pretty(tcx, Some(def_id), &mut handle).is_err() {
             error!(
                 "Cannot print MIR: error while printing `{:?}`",
                 def_id
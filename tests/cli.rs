@@ -0,0 +1,46 @@
+// Command line handling of rsaa
+use std::process::Command;
+
+mod common;
+
+#[test]
+fn every_body_name_is_printed() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsaa"))
+        .args(&["--all"])
+        .arg(common::example("cli/short_names.rs"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("F body -- Start") && stdout.contains("Été body -- Start"));
+}
+
+#[test]
+fn unknown_options_are_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsaa"))
+        .args(&["--fromat", "json"])
+        .arg(common::example("cli/short_names.rs"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown option `--fromat`") && stderr.contains("--format text|json|sarif"), "{}", stderr);
+}
+
+#[test]
+fn sarif_locations_are_relative_to_the_source_root() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsaa"))
+        .args(&["--format", "sarif"])
+        .arg(common::example("tests/pointers.rs"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let run = &sarif["runs"][0];
+    let root = run["originalUriBaseIds"]["SRCROOT"]["uri"].as_str().unwrap();
+    assert!(root.starts_with("file:///") && root.ends_with('/'), "{}", root);
+    let location = &run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"];
+    assert_eq!(location["uri"], "examples/tests/pointers.rs");
+    assert_eq!(location["uriBaseId"], "SRCROOT");
+}
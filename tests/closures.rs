@@ -0,0 +1,10 @@
+// Captures of closures and calls of closure bodies, checked on the programs in examples/closures
+mod common;
+
+// `add` captures `x` by mutable reference and is called with a reference to `x`, `add_y` moves its capture
+#[test]
+fn captured_borrow_aliases_the_argument() {
+    let findings = common::run_rsaa(&common::example("closures/captures.rs"), &[]);
+    assert_eq!(common::lines_of(&findings, "mutable-arguments-alias"), vec![11]);
+    assert_eq!(common::lines_of(&findings, "protector-violation"), vec![10]);
+}
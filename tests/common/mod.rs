@@ -0,0 +1,43 @@
+// Runs rsaa on the example programs, shared by the integration tests
+#![allow(dead_code)] // Each test crate uses a part of the helpers
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+pub fn example(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join(path)
+}
+
+// JSON findings reported in the program itself, the ones in the standard library are left out
+pub fn run_rsaa(program: &Path, extra_args: &[&str]) -> Vec<Value> {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsaa"))
+        .args(&["--format", "json"])
+        .args(extra_args)
+        .arg(program)
+        .output()
+        .unwrap();
+    let findings: Value = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|error| panic!("{}: invalid output {}\n{}", program.display(), error, String::from_utf8_lossy(&output.stderr)));
+    let file = program.to_str().unwrap();
+    findings.as_array().unwrap().iter()
+        .filter(|finding| finding["file"] == file)
+        .cloned()
+        .collect()
+}
+
+// Kind and line of each finding
+pub fn kinds_and_lines(findings: &[Value]) -> Vec<(String, u64)> {
+    findings.iter()
+        .map(|finding| (finding["kind"].as_str().unwrap().to_string(), finding["line"].as_u64().unwrap()))
+        .collect()
+}
+
+// Lines of the findings of one kind
+pub fn lines_of(findings: &[Value], kind: &str) -> Vec<u64> {
+    findings.iter()
+        .filter(|finding| finding["kind"] == kind)
+        .map(|finding| finding["line"].as_u64().unwrap())
+        .collect()
+}
@@ -0,0 +1,139 @@
+// Miri style programs analyzed by rsaa, the ones in fail/ have undefined behavior on the lines marked with UB,
+// or with `UB in <model>` when only one aliasing model forbids them
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+mod common;
+
+const EXAMPLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/stacked_borrows");
+
+fn programs(kind: &str) -> Vec<PathBuf> {
+    let mut programs: Vec<PathBuf> = fs::read_dir(Path::new(EXAMPLES).join(kind))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "rs"))
+        .collect();
+    programs.sort();
+    programs
+}
+
+// Aliasing model and protector violations reported in the program
+fn violations(program: &Path, model: &str, retag: bool) -> Vec<Value> {
+    let mut args = vec!["--model", model];
+    if retag {
+        args.push("--retag");
+    }
+    common::run_rsaa(program, &args).into_iter()
+        .filter(|finding| finding["kind"] == "stacked-borrows-violation" || finding["kind"] == "protector-violation")
+        .collect()
+}
+
+fn violation_lines(program: &Path, model: &str, retag: bool) -> Vec<u64> {
+    let mut lines: Vec<u64> = violations(program, model, retag).iter()
+        .map(|finding| finding["line"].as_u64().unwrap())
+        .collect();
+    lines.sort_unstable();
+    lines
+}
+
+fn ub_lines(program: &Path, model: &str) -> Vec<u64> {
+    let marker = format!("// UB in {}", model);
+    fs::read_to_string(program).unwrap().lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains("// UB") && (!line.contains("// UB in ") || line.contains(&marker)))
+        .map(|(index, _)| index as u64 + 1)
+        .collect()
+}
+
+fn check_fail(model: &str, retag: bool) {
+    for program in programs("fail") {
+        let expected = ub_lines(&program, model);
+        let lines = violation_lines(&program, model, retag);
+        assert_eq!(lines, expected, "{} under {}: violations reported on the wrong lines", program.display(), model);
+    }
+}
+
+fn check_pass(model: &str, retag: bool) {
+    for program in programs("pass") {
+        let lines = violation_lines(&program, model, retag);
+        assert!(lines.is_empty(), "{} under {}: unexpected violations on lines {:?}", program.display(), model, lines);
+    }
+}
+
+#[test]
+fn fail_programs_mark_undefined_behavior() {
+    for program in programs("fail") {
+        assert!(!ub_lines(&program, "stacked-borrows").is_empty(), "{} has no line marked with UB", program.display());
+    }
+}
+
+// The notes of a violation tell where the tag was created and which access invalidated it
+#[test]
+fn violations_include_the_borrow_history() {
+    let program = Path::new(EXAMPLES).join("fail/illegal_write2.rs");
+    let violations = violations(&program, "stacked-borrows", false);
+    let notes: Vec<(u64, &str)> = violations[0]["notes"].as_array().unwrap().iter()
+        .map(|note| (note["line"].as_u64().unwrap(), note["message"].as_str().unwrap()))
+        .collect();
+    assert_eq!(notes, vec![
+        (5, "`target2` was created here as SharedReadWrite from `target`"),
+        (6, "`target2` was invalidated here by an access through `target`"),
+    ]);
+}
+
+// The later use of an argument popped while it was protected is part of the protector violation
+#[test]
+fn protected_invalidation_is_reported_once() {
+    let program = Path::new(EXAMPLES).join("fail/aliasing_mut1.rs");
+    for retag in [false, true] {
+        let violations = violations(&program, "stacked-borrows", retag);
+        let kinds: Vec<&str> = violations.iter().map(|finding| finding["kind"].as_str().unwrap()).collect();
+        assert_eq!(kinds, vec!["protector-violation"]);
+        let notes = violations[0]["notes"].as_array().unwrap();
+        for (index, note) in notes.iter().enumerate() {
+            assert!(!notes[..index].contains(note), "note {} is repeated", note);
+        }
+    }
+}
+
+#[test]
+fn fail_programs_report_violations() {
+    check_fail("stacked-borrows", false);
+}
+
+#[test]
+fn pass_programs_report_nothing() {
+    check_pass("stacked-borrows", false);
+}
+
+#[test]
+fn fail_programs_report_violations_with_retags() {
+    check_fail("stacked-borrows", true);
+}
+
+#[test]
+fn pass_programs_report_nothing_with_retags() {
+    check_pass("stacked-borrows", true);
+}
+
+#[test]
+fn fail_programs_report_violations_under_tree_borrows() {
+    check_fail("tree-borrows", false);
+}
+
+#[test]
+fn pass_programs_report_nothing_under_tree_borrows() {
+    check_pass("tree-borrows", false);
+}
+
+#[test]
+fn fail_programs_report_violations_under_tree_borrows_with_retags() {
+    check_fail("tree-borrows", true);
+}
+
+#[test]
+fn pass_programs_report_nothing_under_tree_borrows_with_retags() {
+    check_pass("tree-borrows", true);
+}
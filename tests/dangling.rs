@@ -0,0 +1,16 @@
+// Uses of pointers to dead storage, checked on the programs in examples/dangling
+mod common;
+
+// A returned pointer to a dropped box, its dereference in the caller, a local out of scope and a dropped box
+#[test]
+fn pointers_to_dead_storage_are_reported() {
+    let findings = common::run_rsaa(&common::example("dangling/pointers.rs"), &[]);
+    assert_eq!(common::lines_of(&findings, "dangling-pointer"), vec![7, 11, 21, 28]);
+}
+
+// The storage marker at the end of the scope kills `x`, the dereference after it has no access left
+#[test]
+fn scope_exit_ends_the_borrow_stack() {
+    let findings = common::run_rsaa(&common::example("dangling/pointers.rs"), &[]);
+    assert_eq!(common::lines_of(&findings, "stacked-borrows-violation"), vec![21, 28]);
+}
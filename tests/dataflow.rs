@@ -0,0 +1,27 @@
+// States joined at merge points and loops that reach a fixpoint, checked on the programs in examples/dataflow
+mod common;
+
+// Kind and line of the findings in the program, in strict mode so that a block that does not converge is reported
+fn findings(name: &str) -> Vec<(String, u64)> {
+    common::kinds_and_lines(&common::run_rsaa(&common::example(&format!("dataflow/{}", name)), &["--strict"]))
+}
+
+#[test]
+fn invalidation_in_one_branch_reaches_the_merge_point() {
+    assert_eq!(findings("merge_invalidates.rs"), vec![(String::from("stacked-borrows-violation"), 8)]);
+}
+
+#[test]
+fn merge_point_keeps_a_borrow_valid_in_both_branches() {
+    assert_eq!(findings("merge_keeps.rs"), vec![]);
+}
+
+#[test]
+fn loop_exit_refines_the_widened_counter() {
+    assert_eq!(findings("loop_counter.rs"), vec![]);
+}
+
+#[test]
+fn widened_counter_may_reach_the_exit_bound() {
+    assert_eq!(findings("loop_zero.rs"), vec![(String::from("division-by-zero"), 7)]);
+}
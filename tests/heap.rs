@@ -0,0 +1,26 @@
+// Heap objects of Box, Vec and Rc, checked on the programs in examples/heap
+mod common;
+
+// Raw pointers into a box or the buffer of a vector are invalidated by accesses through their owner
+#[test]
+fn owner_access_invalidates_pointers_into_the_object() {
+    let findings = common::run_rsaa(&common::example("heap/objects.rs"), &[]);
+    assert_eq!(common::lines_of(&findings, "stacked-borrows-violation"), vec![10, 25, 33]);
+}
+
+// `drop` of a vector moved out of a borrowed local frees the buffer
+#[test]
+fn dropped_vector_frees_its_buffer() {
+    let findings = common::run_rsaa(&common::example("heap/objects.rs"), &[]);
+    assert_eq!(common::lines_of(&findings, "dangling-pointer"), vec![33]);
+}
+
+// Two boxes are different objects, two Rc pointers share one
+#[test]
+fn allocation_sites_are_different_objects() {
+    let findings = common::run_rsaa(&common::example("heap/objects.rs"), &[]);
+    let may_alias = common::lines_of(&findings, "may-alias");
+    assert!(!may_alias.contains(&13) && !may_alias.contains(&14));
+    assert!(findings.iter().any(|finding| finding["kind"] == "may-alias" && finding["line"] == 36
+        && finding["message"].as_str().unwrap().contains("`Rc#")));
+}
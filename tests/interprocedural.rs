@@ -0,0 +1,49 @@
+// Callees resolved and summarized at the call sites, checked on the programs in examples/interprocedural
+use serde_json::Value;
+
+mod common;
+
+fn findings(name: &str) -> Vec<Value> {
+    common::run_rsaa(&common::example(&format!("interprocedural/{}", name)), &[])
+}
+
+#[test]
+fn summary_of_a_helper_is_applied_at_the_call_site() {
+    let findings = findings("summaries.rs");
+    assert_eq!(common::lines_of(&findings, "mutable-arguments-alias"), vec![20]);
+}
+
+// The summary of `pick` is only stable after it swapped its arguments, in strict mode a cycle that does not
+// converge would be reported
+#[test]
+fn recursive_summaries_reach_a_fixpoint() {
+    let findings = common::run_rsaa(&common::example("interprocedural/recursion.rs"), &["--strict"]);
+    assert_eq!(common::lines_of(&findings, "mutable-arguments-alias"), vec![41]);
+    assert!(common::lines_of(&findings, "unsupported").is_empty());
+}
+
+// `update` accesses its arguments only in the impl for `Add`, and `first::<u8>` returns the first field
+#[test]
+fn generic_callees_are_analyzed_in_the_concrete_body() {
+    let findings = findings("generic_callees.rs");
+    let protected = common::lines_of(&findings, "protector-violation");
+    assert!(protected.contains(&11) && !protected.contains(&16), "{:?}", protected);
+    assert!(common::lines_of(&findings, "mutable-arguments-alias").contains(&45));
+}
+
+// The generic impl is selected for `Split<u8>`, and `double` is reached through both copies of the
+// function pointer but analyzed once for the call
+#[test]
+fn dynamic_calls_execute_the_bodies_they_may_hold() {
+    let findings = common::run_rsaa(&common::example("interprocedural/dynamic_calls.rs"), &["--strict"]);
+    assert_eq!(common::lines_of(&findings, "division-by-zero"), vec![23]);
+    assert_eq!(common::lines_of(&findings, "protector-violation"), vec![32]);
+    assert!(common::lines_of(&findings, "unsupported").is_empty());
+}
+
+// Analyzed on its own, nothing is known about the receiver of `update_all`
+#[test]
+fn dynamic_call_with_an_unknown_receiver_is_unsupported() {
+    let findings = common::run_rsaa(&common::example("interprocedural/dynamic_calls.rs"), &["--strict", "--all"]);
+    assert_eq!(common::lines_of(&findings, "unsupported"), vec![28]);
+}
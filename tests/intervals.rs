@@ -0,0 +1,140 @@
+// Interval arithmetic, guard refinement and widening of the values of integer places
+#![feature(rustc_private)]
+
+extern crate rustc_middle;
+
+use rustc_middle::mir::BinOp;
+use static_alias_analyzer::intervals::{Condition, Interval, IntervalState};
+use static_alias_analyzer::places::PlacePath;
+
+mod common;
+
+const I32: Interval = Interval { lo: i32::MIN as i128, hi: i32::MAX as i128 };
+
+fn interval(lo: i128, hi: i128) -> Interval {
+    Interval { lo, hi }
+}
+
+fn local(local: u32) -> PlacePath {
+    PlacePath::from_local(local)
+}
+
+// State where _1 holds the values of the interval and _2 the result of comparing _1 with a constant
+fn guard(values: Interval, op: BinOp, constant: i128) -> IntervalState {
+    let mut state = IntervalState::default();
+    state.assign(&local(1), values);
+    state.assign(&local(2), Interval::boolean());
+    state.add_condition(&local(2), Condition { op, subject: local(1), constant });
+    state
+}
+
+#[test]
+fn guard_excludes_zero() {
+    let mut state = guard(interval(0, 10), BinOp::Ne, 0);
+    assert_eq!(state.refine(&local(2), true), Some(()));
+    assert_eq!(state.get(&local(1)), interval(1, 10));
+
+    let mut state = guard(interval(0, 10), BinOp::Ne, 0);
+    assert_eq!(state.refine(&local(2), false), Some(()));
+    assert_eq!(state.get(&local(1)), Interval::constant(0));
+}
+
+#[test]
+fn guard_excludes_zero_from_any_value() {
+    let mut state = guard(Interval::top(), BinOp::Ne, 0);
+    assert_eq!(state.refine(&local(2), true), Some(()));
+    assert!(state.get(&local(1)).is_top());
+    assert!(state.excludes(&local(1), 0));
+    state.add_copy(&local(3), &local(1));
+    assert!(state.excludes(&local(3), 0));
+
+    // Only a value ruled out in both paths stays excluded after the join
+    let mut other = guard(Interval::top(), BinOp::Ne, 0);
+    state.join(&other);
+    assert!(!state.excludes(&local(1), 0));
+    assert_eq!(other.refine(&local(2), true), Some(()));
+    let mut both = other.clone();
+    both.join(&other);
+    assert!(both.excludes(&local(1), 0));
+}
+
+#[test]
+fn guard_restricts_the_subject() {
+    let mut state = guard(interval(0, 100), BinOp::Lt, 10);
+    assert_eq!(state.refine(&local(2), true), Some(()));
+    assert_eq!(state.get(&local(1)), interval(0, 9));
+
+    let mut state = guard(interval(0, 100), BinOp::Lt, 10);
+    assert_eq!(state.refine(&local(2), false), Some(()));
+    assert_eq!(state.get(&local(1)), interval(10, 100));
+}
+
+#[test]
+fn guard_that_can_not_hold_makes_the_edge_unreachable() {
+    let mut state = guard(interval(20, 30), BinOp::Lt, 10);
+    assert_eq!(state.refine(&local(2), true), None);
+}
+
+#[test]
+fn only_a_bound_can_be_excluded() {
+    assert_eq!(interval(-5, 5).exclude(0), Some(interval(-5, 5)));
+    assert_eq!(interval(0, 5).exclude(0), Some(interval(1, 5)));
+    assert_eq!(interval(-5, 0).exclude(0), Some(interval(-5, -1)));
+    assert_eq!(Interval::constant(0).exclude(0), None);
+}
+
+#[test]
+fn widening_jumps_to_infinity() {
+    assert_eq!(interval(0, 1).widen(&interval(0, 2)), interval(0, i128::MAX));
+    assert_eq!(interval(0, 1).widen(&interval(-1, 1)), interval(i128::MIN, 1));
+    assert_eq!(interval(0, 1).widen(&interval(0, 1)), interval(0, 1));
+
+    let mut previous = IntervalState::default();
+    previous.assign(&local(1), interval(0, 1));
+    previous.assign(&local(2), interval(5, 5));
+    let mut next = previous.clone();
+    next.assign(&local(1), interval(0, 2));
+    next.widen(&previous);
+    assert_eq!(next.get(&local(1)), interval(0, i128::MAX));
+    assert_eq!(next.get(&local(2)), interval(5, 5));
+}
+
+#[test]
+fn overflow_at_the_bounds_of_the_type_is_any_value() {
+    let max = I32.hi;
+    assert_eq!(Interval::binary_op(BinOp::Add, &interval(0, max - 1), &Interval::constant(1), &I32), interval(1, max));
+    assert!(Interval::binary_op(BinOp::Add, &interval(0, max), &Interval::constant(1), &I32).is_top());
+    assert!(Interval::binary_op(BinOp::Sub, &interval(I32.lo, 0), &Interval::constant(1), &I32).is_top());
+    assert!(Interval::binary_op(BinOp::Mul, &interval(0, 1 << 16), &interval(0, 1 << 16), &I32).is_top());
+}
+
+#[test]
+fn widened_bounds_are_the_bounds_of_the_type() {
+    let widened = interval(10, i128::MAX);
+    assert_eq!(Interval::binary_op(BinOp::Sub, &widened, &Interval::constant(10), &I32), interval(0, I32.hi - 10));
+    assert!(Interval::binary_op(BinOp::Add, &widened, &Interval::constant(1), &I32).is_top());
+}
+
+#[test]
+fn division_by_a_divisor_that_may_be_zero_is_any_value() {
+    assert_eq!(Interval::binary_op(BinOp::Div, &interval(10, 20), &interval(1, 2), &I32), interval(5, 20));
+    assert!(Interval::binary_op(BinOp::Div, &interval(10, 20), &interval(-1, 1), &I32).is_top());
+    assert!(Interval::binary_op(BinOp::Div, &interval(10, 20), &Interval::top(), &I32).is_top());
+    assert_eq!(Interval::compare(BinOp::Eq, &Interval::top(), &Interval::constant(0)), Interval::boolean());
+}
+
+#[test]
+fn comparison_is_known_when_the_intervals_do_not_overlap() {
+    assert_eq!(Interval::compare(BinOp::Lt, &interval(0, 9), &Interval::constant(10)), Interval::constant(1));
+    assert_eq!(Interval::compare(BinOp::Lt, &interval(10, 20), &Interval::constant(10)), Interval::constant(0));
+    assert_eq!(Interval::compare(BinOp::Ne, &interval(1, 5), &Interval::constant(0)), Interval::constant(1));
+    assert_eq!(Interval::compare(BinOp::Le, &interval(0, 10), &Interval::constant(5)), Interval::boolean());
+}
+
+// Division findings of the program, the divisor of `unknown` is a top interval
+#[test]
+fn division_by_an_unknown_divisor_is_reported() {
+    let findings = common::run_rsaa(&common::example("intervals/unknown_divisor.rs"), &["--all"]);
+    let lines = common::lines_of(&findings, "division-by-zero");
+    assert_eq!(lines, vec![3]);
+}
@@ -0,0 +1,22 @@
+// Size and validity checks of casts and transmutes, checked on the programs in examples/layout
+use serde_json::Value;
+
+mod common;
+
+// Kind and line of the cast and transmute findings in the program
+fn findings(name: &str) -> Vec<(String, u64)> {
+    let findings: Vec<Value> = common::run_rsaa(&common::example(&format!("layout/{}", name)), &[]).into_iter()
+        .filter(|finding| finding["kind"].as_str().map_or(false, |kind| kind.contains("cast") || kind.contains("transmute")))
+        .collect();
+    common::kinds_and_lines(&findings)
+}
+
+#[test]
+fn casts_compare_pointees_only_between_pointers() {
+    assert_eq!(findings("casts.rs"), vec![(String::from("cast-size"), 6), (String::from("cast-size"), 7)]);
+}
+
+#[test]
+fn transmutes_into_enums_with_fields_are_not_checked() {
+    assert_eq!(findings("transmute_option.rs"), vec![(String::from("invalid-transmute"), 13)]);
+}
@@ -0,0 +1,86 @@
+// Field sensitive aliasing of the points-to graph, places behind a deref are reached through their pointer
+use static_alias_analyzer::places::{PathElem, PlacePath};
+use static_alias_analyzer::points_to::PointsToGraph;
+
+mod common;
+
+fn local(local: u32) -> PlacePath {
+    PlacePath::from_local(local)
+}
+
+// (*pointer).field
+fn deref_field(pointer: u32, field: u32) -> PlacePath {
+    PlacePath { projection: vec![PathElem::Deref, PathElem::Field(field)], ..local(pointer) }
+}
+
+fn field(base: u32, field: u32) -> PlacePath {
+    PlacePath { projection: vec![PathElem::Field(field)], ..local(base) }
+}
+
+#[test]
+fn disjoint_fields_through_an_argument_do_not_alias() {
+    // fn split(t: &mut (i32, i32)) { let x = &mut t.0; let y = &mut t.1; }
+    let mut graph = PointsToGraph::new();
+    graph.borrow(&local(2), &deref_field(1, 0));
+    graph.borrow(&local(3), &deref_field(1, 1));
+    assert!(!graph.are_alias(&local(2), &local(3)));
+    assert!(graph.aliasing_test(|_| true).is_empty());
+}
+
+#[test]
+fn disjoint_fields_through_a_local_reference_do_not_alias() {
+    let mut graph = PointsToGraph::new();
+    graph.borrow(&local(1), &local(4));
+    graph.borrow(&local(2), &deref_field(1, 0));
+    graph.borrow(&local(3), &deref_field(1, 1));
+    assert!(!graph.are_alias(&local(2), &local(3)));
+    assert!(graph.are_alias(&local(2), &field(4, 0)));
+    assert!(!graph.are_alias(&local(3), &field(4, 0)));
+}
+
+#[test]
+fn same_field_through_a_deref_aliases() {
+    let mut graph = PointsToGraph::new();
+    graph.borrow(&local(1), &local(4));
+    graph.borrow(&local(2), &deref_field(1, 0));
+    graph.borrow(&local(3), &field(4, 0));
+    assert!(graph.are_alias(&local(2), &local(3)));
+}
+
+#[test]
+fn copy_of_a_borrowed_value_is_not_a_borrower() {
+    // let p = &x; let moved = x; let q = &x;
+    let mut graph = PointsToGraph::new();
+    graph.borrow(&local(2), &local(1));
+    graph.points_to(&local(3), &local(1));
+    assert!(graph.aliasing_test(|_| true).is_empty());
+    graph.borrow(&local(4), &local(1));
+    assert_eq!(graph.aliasing_test(|_| true), vec![local(1)]);
+    assert!(graph.aliasing_test(|borrower| *borrower != local(2)).is_empty());
+}
+
+#[test]
+fn borrow_through_a_dead_temporary_borrows_its_pointee() {
+    // let r1 = &raw const *&num; let r2 = &raw mut *&mut num;
+    let deref = |pointer: u32| PlacePath { projection: vec![PathElem::Deref], ..local(pointer) };
+    let mut graph = PointsToGraph::new();
+    graph.borrow(&local(3), &local(1));
+    graph.borrow(&local(2), &deref(3));
+    graph.borrow(&local(5), &local(1));
+    graph.borrow(&local(4), &deref(5));
+    let temporaries = [local(3), local(5)];
+    assert_eq!(graph.aliasing_test(|place| !temporaries.contains(place)), vec![local(1)]);
+
+    // A reborrow through a live pointer is not another borrower of its pointee
+    let mut graph = PointsToGraph::new();
+    graph.borrow(&local(2), &local(1));
+    graph.borrow(&local(3), &deref(2));
+    assert!(graph.aliasing_test(|_| true).is_empty());
+}
+
+// Single borrows, borrows given to a call, moved values and derived code are not reported
+#[test]
+fn only_memory_with_two_live_borrowers_is_reported() {
+    let findings = common::run_rsaa(&common::example("aliasing/borrowers.rs"), &[]);
+    assert_eq!(common::lines_of(&findings, "may-alias"), vec![28]);
+}
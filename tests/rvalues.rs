@@ -0,0 +1,15 @@
+// Rvalues the analysis approximates, checked on the programs in examples/rvalues
+mod common;
+
+// A repeated array, the length of a slice and a thread local are each reported in strict mode
+#[test]
+fn approximated_rvalues_are_unsupported_in_strict_mode() {
+    let findings = common::run_rsaa(&common::example("rvalues/approximated.rs"), &["--strict"]);
+    assert_eq!(common::lines_of(&findings, "unsupported"), vec![9, 14, 16]);
+}
+
+#[test]
+fn approximated_rvalues_are_analyzed_without_strict_mode() {
+    let findings = common::run_rsaa(&common::example("rvalues/approximated.rs"), &[]);
+    assert!(findings.is_empty());
+}
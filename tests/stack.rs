@@ -0,0 +1,247 @@
+// Grant and access rules of a single borrow stack, after the cases of Miri's stacked borrows tests
+use static_alias_analyzer::aliasing_model::AliasingModel;
+use static_alias_analyzer::stacked_borrows::{Access, Creation, Invalidation, Permission, Stack, StackItem, Tag, Violation};
+
+use Permission::{Disabled, SharedReadOnly, SharedReadWrite, Unique};
+
+fn tag(id: u32) -> Tag {
+    Tag::Tagged(id)
+}
+
+// Stack of a new allocation owned by tag 0
+fn allocation() -> Stack {
+    let mut stack = Stack::new();
+    stack.new_ref(tag(0), Unique);
+    stack
+}
+
+fn items(stack: &Stack) -> Vec<(u32, Permission)> {
+    stack.items().iter().map(|item| match item.tag() {
+        Tag::Tagged(id) => (id, item.permission()),
+        Tag::Untagged => (u32::MAX, item.permission()),
+    }).collect()
+}
+
+fn violation(id: u32, access: Access) -> Violation {
+    Violation { tag: tag(id), access }
+}
+
+#[test]
+fn unique_reborrow_is_pushed_on_top() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(2), Unique);
+    assert_eq!(items(&stack), vec![(0, Unique), (1, Unique), (2, Unique)]);
+    assert!(stack.violations.is_empty());
+}
+
+#[test]
+fn write_pops_items_above_granting_item() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(2), SharedReadOnly);
+    stack.write(tag(1));
+    assert_eq!(items(&stack), vec![(0, Unique), (1, Unique)]);
+    assert_eq!(stack.invalidations.len(), 1);
+    stack.read(tag(2));
+    assert_eq!(stack.violations, vec![violation(2, Access::Read)]);
+}
+
+#[test]
+fn read_disables_unique_items_above_granting_item() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.grant(tag(1), tag(2), Unique);
+    stack.grant(tag(2), tag(3), SharedReadOnly);
+    stack.read(tag(1));
+    assert_eq!(items(&stack), vec![(0, Unique), (1, SharedReadWrite), (2, Disabled), (3, SharedReadOnly)]);
+    assert!(!stack.is_live(tag(2)));
+    stack.read(tag(3));
+    stack.read(tag(2));
+    assert_eq!(stack.violations, vec![violation(2, Access::Read)]);
+}
+
+#[test]
+fn shared_read_only_does_not_grant_writes() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadOnly);
+    stack.read(tag(1));
+    stack.write(tag(1));
+    assert_eq!(stack.violations, vec![violation(1, Access::Write)]);
+}
+
+#[test]
+fn write_reborrow_from_shared_read_only_fails() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadOnly);
+    stack.grant(tag(1), tag(2), SharedReadWrite);
+    assert_eq!(stack.violations, vec![violation(1, Access::Write)]);
+}
+
+#[test]
+fn shared_read_write_is_inserted_next_to_its_parent() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(2), Unique);
+    stack.grant(tag(1), tag(3), SharedReadWrite);
+    // No access through the parent, the item above it is not popped
+    assert_eq!(items(&stack), vec![(0, Unique), (1, Unique), (3, SharedReadWrite), (2, Unique)]);
+    assert!(stack.invalidations.is_empty());
+}
+
+#[test]
+fn sibling_shared_read_write_items_survive_each_others_writes() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(2), SharedReadWrite);
+    stack.grant(tag(1), tag(3), SharedReadWrite);
+    stack.write(tag(2));
+    stack.write(tag(3));
+    stack.write(tag(2));
+    assert!(stack.violations.is_empty());
+    assert!(stack.is_live(tag(2)) && stack.is_live(tag(3)));
+}
+
+#[test]
+fn write_through_shared_read_write_pops_unique_above() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.grant(tag(1), tag(2), Unique);
+    stack.write(tag(1));
+    assert_eq!(items(&stack), vec![(0, Unique), (1, SharedReadWrite)]);
+    stack.write(tag(2));
+    assert_eq!(stack.violations, vec![violation(2, Access::Write)]);
+}
+
+#[test]
+fn unique_reborrow_pops_raw_pointer_above_parent() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(2), SharedReadWrite);
+    stack.grant(tag(1), tag(3), Unique);
+    assert_eq!(items(&stack), vec![(0, Unique), (1, Unique), (3, Unique)]);
+    stack.write(tag(2));
+    assert_eq!(stack.violations, vec![violation(2, Access::Write)]);
+}
+
+#[test]
+fn access_through_owner_invalidates_every_reborrow() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(2), SharedReadOnly);
+    stack.grant(tag(1), tag(3), SharedReadOnly);
+    stack.read(tag(2));
+    stack.read(tag(3));
+    stack.write(tag(0));
+    assert_eq!(items(&stack), vec![(0, Unique)]);
+    stack.read(tag(2));
+    assert_eq!(stack.violations, vec![violation(2, Access::Read)]);
+}
+
+#[test]
+fn raw_pointer_copy_shares_the_items_of_the_original() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.share(tag(1), tag(2));
+    stack.write(tag(1));
+    stack.write(tag(2));
+    stack.write(tag(1));
+    assert!(stack.violations.is_empty());
+    assert_eq!(items(&stack), vec![(0, Unique), (1, SharedReadWrite), (2, SharedReadWrite)]);
+}
+
+#[test]
+fn failed_reborrow_is_reported_once() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.write(tag(0));
+    stack.grant(tag(1), tag(2), Unique);
+    stack.write(tag(2));
+    assert_eq!(stack.violations, vec![violation(1, Access::Write)]);
+}
+
+#[test]
+fn reborrow_is_not_duplicated() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.grant(tag(1), tag(1), Unique);
+    assert_eq!(stack.items(), &[StackItem::new(tag(0), Unique), StackItem::new(tag(1), Unique)]);
+}
+
+fn protected_invalidations(stack: &Stack) -> Vec<Invalidation> {
+    stack.invalidations.iter().filter(|invalidation| invalidation.protected).cloned().collect()
+}
+
+#[test]
+fn popping_a_protected_item_is_reported() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.grant(tag(1), tag(2), Unique);
+    stack.protect(tag(2));
+    assert!(stack.items()[2].is_protected());
+    stack.write(tag(1));
+    assert_eq!(protected_invalidations(&stack), vec![Invalidation { tag: tag(2), invalidated_by: tag(1), protected: true }]);
+}
+
+#[test]
+fn disabling_a_protected_item_is_reported_once() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.grant(tag(1), tag(2), Unique);
+    stack.protect(tag(2));
+    stack.read(tag(1));
+    stack.write(tag(1));
+    assert_eq!(protected_invalidations(&stack).len(), 1);
+    assert_eq!(stack.invalidations.len(), 2);
+}
+
+#[test]
+fn accesses_above_a_protected_item_are_allowed() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.protect(tag(1));
+    stack.grant(tag(1), tag(2), Unique);
+    stack.write(tag(2));
+    stack.write(tag(1));
+    assert!(protected_invalidations(&stack).is_empty());
+    assert_eq!(stack.invalidations.len(), 1);
+}
+
+#[test]
+fn new_items_are_recorded_with_their_parent() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), Unique);
+    stack.share(tag(1), tag(2));
+    stack.grant(tag(1), tag(1), Unique);
+    assert_eq!(stack.take_creations(), vec![
+        Creation { tag: tag(0), derived_from: None, permission: Unique },
+        Creation { tag: tag(1), derived_from: Some(tag(0)), permission: Unique },
+        Creation { tag: tag(2), derived_from: Some(tag(1)), permission: Unique },
+    ]);
+}
+
+#[test]
+fn wildcard_access_uses_the_topmost_exposed_item() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.expose(tag(1));
+    stack.grant(tag(1), tag(2), Unique);
+    stack.write(Tag::Untagged);
+    assert!(stack.violations.is_empty());
+    assert!(stack.items()[1].is_exposed());
+    assert_eq!(items(&stack), vec![(0, Unique), (1, SharedReadWrite)]);
+    assert_eq!(stack.invalidations, vec![Invalidation { tag: tag(2), invalidated_by: Tag::Untagged, protected: false }]);
+}
+
+#[test]
+fn wildcard_access_without_exposed_item_fails() {
+    let mut stack = allocation();
+    stack.grant(tag(0), tag(1), SharedReadWrite);
+    stack.expose(tag(1));
+    stack.write(tag(0));
+    stack.grant(tag(0), tag(2), SharedReadOnly);
+    stack.expose(tag(2));
+    stack.read(Tag::Untagged);
+    stack.write(Tag::Untagged);
+    assert_eq!(stack.violations, vec![Violation { tag: Tag::Untagged, access: Access::Write }]);
+}
@@ -0,0 +1,24 @@
+// Statements other than assignments, checked on the programs in examples/statements
+mod common;
+
+// The write through the destination pops `shared`, the constant source is reported in strict mode
+#[test]
+fn copy_nonoverlapping_accesses_both_pointers() {
+    let findings = common::run_rsaa(&common::example("statements/copy_nonoverlapping.rs"), &["--strict"]);
+    assert_eq!(common::lines_of(&findings, "stacked-borrows-violation"), vec![19]);
+    assert_eq!(common::lines_of(&findings, "unsupported"), vec![22]);
+}
+
+// StorageLive gives `x` a new borrow stack in every iteration
+#[test]
+fn storage_live_resets_the_borrow_stack() {
+    let findings = common::run_rsaa(&common::example("statements/storage_markers.rs"), &["--strict"]);
+    assert_eq!(common::kinds_and_lines(&findings), vec![(String::from("stacked-borrows-violation"), 8)]);
+}
+
+// Retags of the arguments of `first` use their pointees, strict mode reports the retags of unknown pointees
+#[test]
+fn retags_without_a_pointee_are_unsupported_in_strict_mode() {
+    let findings = common::run_rsaa(&common::example("statements/retags.rs"), &["--retag", "--strict"]);
+    assert_eq!(common::lines_of(&findings, "unsupported"), vec![9, 12]);
+}
@@ -0,0 +1,169 @@
+// Reborrow and access rules of a single borrow tree, after the cases of Miri's tree borrows tests
+use static_alias_analyzer::aliasing_model::AliasingModel;
+use static_alias_analyzer::stacked_borrows::{Access, Permission, Tag, Violation};
+use static_alias_analyzer::tree_borrows::{Tree, TreePermission};
+
+use Permission::{SharedReadOnly, SharedReadWrite, Unique};
+use TreePermission::{Active, Disabled, Frozen, Reserved};
+
+fn tag(id: u32) -> Tag {
+    Tag::Tagged(id)
+}
+
+// Tree of a new allocation owned by tag 0
+fn allocation() -> Tree {
+    let mut tree = Tree::new();
+    tree.new_ref(tag(0), Unique);
+    tree
+}
+
+fn violation(id: u32, access: Access) -> Violation {
+    Violation { tag: tag(id), access }
+}
+
+#[test]
+fn reborrows_are_children_of_their_parent() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.grant(tag(1), tag(2), SharedReadOnly);
+    assert_eq!(tree.parent(tag(1)), Some(tag(0)));
+    assert_eq!(tree.parent(tag(2)), Some(tag(1)));
+    assert_eq!(tree.permission(tag(1)), Some(Reserved));
+    assert_eq!(tree.permission(tag(2)), Some(Frozen));
+}
+
+#[test]
+fn write_activates_the_node_and_its_parents() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.grant(tag(1), tag(2), Unique);
+    tree.write(tag(2));
+    assert_eq!(tree.permission(tag(0)), Some(Active));
+    assert_eq!(tree.permission(tag(1)), Some(Active));
+    assert_eq!(tree.permission(tag(2)), Some(Active));
+    assert!(tree.violations.is_empty());
+}
+
+#[test]
+fn foreign_read_keeps_reserved_writable() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.read(tag(0));
+    assert_eq!(tree.permission(tag(1)), Some(Reserved));
+    tree.write(tag(1));
+    assert!(tree.violations.is_empty());
+}
+
+#[test]
+fn foreign_read_freezes_active() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.write(tag(1));
+    tree.read(tag(0));
+    assert_eq!(tree.permission(tag(1)), Some(Frozen));
+    tree.read(tag(1));
+    tree.write(tag(1));
+    assert_eq!(tree.violations, vec![violation(1, Access::Write)]);
+}
+
+#[test]
+fn foreign_write_disables_the_other_nodes() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.grant(tag(0), tag(2), SharedReadOnly);
+    tree.write(tag(0));
+    assert!(!tree.is_live(tag(1)) && !tree.is_live(tag(2)));
+    assert_eq!(tree.invalidations.len(), 2);
+    tree.read(tag(2));
+    assert_eq!(tree.violations, vec![violation(2, Access::Read)]);
+}
+
+#[test]
+fn write_through_a_child_keeps_the_parent_usable() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.grant(tag(1), tag(2), Unique);
+    tree.write(tag(2));
+    tree.write(tag(1));
+    assert_eq!(tree.permission(tag(2)), Some(Disabled));
+    assert!(tree.violations.is_empty());
+}
+
+#[test]
+fn frozen_does_not_grant_writes_to_its_children() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), SharedReadOnly);
+    tree.grant(tag(1), tag(2), Unique);
+    tree.write(tag(2));
+    assert_eq!(tree.violations, vec![violation(2, Access::Write)]);
+}
+
+#[test]
+fn raw_pointers_use_the_node_of_their_parent() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.grant(tag(1), tag(2), SharedReadWrite);
+    tree.share(tag(2), tag(3));
+    tree.grant(tag(3), tag(4), Unique);
+    assert_eq!(tree.parent(tag(4)), Some(tag(1)));
+    tree.write(tag(3));
+    assert_eq!(tree.permission(tag(1)), Some(Active));
+    assert!(!tree.is_live(tag(4)));
+    tree.write(tag(2));
+    assert!(tree.violations.is_empty());
+}
+
+#[test]
+fn failed_reborrow_is_reported_once() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), SharedReadOnly);
+    tree.write(tag(0));
+    tree.grant(tag(1), tag(2), Unique);
+    tree.write(tag(2));
+    assert_eq!(tree.violations, vec![violation(1, Access::Read)]);
+}
+
+#[test]
+fn disabling_a_protected_node_is_reported() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.protect(tag(1));
+    tree.read(tag(0));
+    assert!(tree.invalidations.is_empty());
+    tree.write(tag(0));
+    assert_eq!(tree.invalidations.iter().filter(|invalidation| invalidation.protected).count(), 1);
+}
+
+#[test]
+fn join_keeps_the_least_permission() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    let mut other = tree.clone();
+    other.write(tag(1));
+    other.read(tag(0));
+    tree.join_keeping(&other, &Default::default());
+    assert_eq!(tree.permission(tag(1)), Some(Frozen));
+}
+
+#[test]
+fn wildcard_access_keeps_the_exposed_nodes() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), Unique);
+    tree.expose(tag(1));
+    tree.grant(tag(1), tag(2), Unique);
+    tree.grant(tag(0), tag(3), SharedReadOnly);
+    tree.write(Tag::Untagged);
+    assert!(tree.violations.is_empty());
+    assert!(tree.is_live(tag(1)));
+    assert!(!tree.is_live(tag(2)) && !tree.is_live(tag(3)));
+}
+
+#[test]
+fn wildcard_access_without_exposed_node_fails() {
+    let mut tree = allocation();
+    tree.grant(tag(0), tag(1), SharedReadOnly);
+    tree.expose(tag(1));
+    tree.read(Tag::Untagged);
+    tree.write(Tag::Untagged);
+    assert_eq!(tree.violations, vec![Violation { tag: Tag::Untagged, access: Access::Write }]);
+}